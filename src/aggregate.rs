@@ -0,0 +1,173 @@
+//! # Aggregate
+//!
+//! Fold trees bottom-up, aggregating the values of each subtree
+
+use crate::{Node, Tree};
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Fold the [`Node`] bottom-up: leaves are aggregated with `leaf_fn`, while every other node is aggregated
+    /// by `combine_fn` from the node itself and the aggregates of its children, in order.
+    /// Returns the aggregate of this node
+    ///
+    /// ```rust
+    /// use orange_trees::Node;
+    ///
+    /// // sizes of files and directories
+    /// let node: Node<&str, u64> = Node::new("/", 0)
+    ///     .with_child(Node::new("/a.txt", 10))
+    ///     .with_child(Node::new("/bin", 1).with_child(Node::new("/bin/ls", 100)));
+    /// let total = node.fold_up(
+    ///     |x| *x.value(),
+    ///     |x, children| x.value() + children.iter().sum::<u64>(),
+    /// );
+    /// assert_eq!(total, 111);
+    /// ```
+    pub fn fold_up<A, L, C>(&self, mut leaf_fn: L, mut combine_fn: C) -> A
+    where
+        L: FnMut(&Self) -> A,
+        C: FnMut(&Self, &[A]) -> A,
+    {
+        /// Private recursive call for fold_up
+        fn fold_up_r<U, T, A, L, C>(node: &Node<U, T>, leaf_fn: &mut L, combine_fn: &mut C) -> A
+        where
+            U: PartialEq,
+            L: FnMut(&Node<U, T>) -> A,
+            C: FnMut(&Node<U, T>, &[A]) -> A,
+        {
+            if node.children.is_empty() {
+                return leaf_fn(node);
+            }
+            let children: Vec<A> = node
+                .children
+                .iter()
+                .map(|x| fold_up_r(x, leaf_fn, combine_fn))
+                .collect();
+            combine_fn(node, &children)
+        }
+        fold_up_r(self, &mut leaf_fn, &mut combine_fn)
+    }
+
+    /// Fold the [`Node`] bottom-up as [`Node::fold_up`] does, returning a node shaped like this one
+    /// where each value is the aggregate of the subtree
+    pub fn aggregate_into<A, L, C>(&self, mut leaf_fn: L, mut combine_fn: C) -> Node<U, A>
+    where
+        U: Clone,
+        A: Clone,
+        L: FnMut(&Self) -> A,
+        C: FnMut(&Self, &[A]) -> A,
+    {
+        /// Private recursive call for aggregate_into
+        fn aggregate_into_r<U, T, A, L, C>(
+            node: &Node<U, T>,
+            leaf_fn: &mut L,
+            combine_fn: &mut C,
+        ) -> Node<U, A>
+        where
+            U: PartialEq + Clone,
+            A: Clone,
+            L: FnMut(&Node<U, T>) -> A,
+            C: FnMut(&Node<U, T>, &[A]) -> A,
+        {
+            if node.children.is_empty() {
+                return Node::new(node.id.clone(), leaf_fn(node));
+            }
+            let children: Vec<Node<U, A>> = node
+                .children
+                .iter()
+                .map(|x| aggregate_into_r(x, leaf_fn, combine_fn))
+                .collect();
+            let aggregates: Vec<A> = children.iter().map(|x| x.value.clone()).collect();
+            Node {
+                id: node.id.clone(),
+                value: combine_fn(node, &aggregates),
+                children,
+            }
+        }
+        aggregate_into_r(self, &mut leaf_fn, &mut combine_fn)
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Fold the [`Tree`] bottom-up, returning the aggregate of the root. See [`Node::fold_up`]
+    pub fn fold_up<A, L, C>(&self, leaf_fn: L, combine_fn: C) -> A
+    where
+        L: FnMut(&Node<U, T>) -> A,
+        C: FnMut(&Node<U, T>, &[A]) -> A,
+    {
+        self.root.fold_up(leaf_fn, combine_fn)
+    }
+
+    /// Fold the [`Tree`] bottom-up into a tree with the aggregate of each subtree. See [`Node::aggregate_into`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let tree: Tree<&str, u64> = Tree::new(
+    ///     Node::new("/", 0)
+    ///         .with_child(Node::new("/a.txt", 10))
+    ///         .with_child(Node::new("/bin", 0).with_child(Node::new("/bin/ls", 100))),
+    /// );
+    /// let sizes = tree.aggregate_into(|x| *x.value(), |_, children| children.iter().sum::<u64>());
+    /// assert_eq!(sizes.root().value(), &110);
+    /// assert_eq!(sizes.root().query(&"/bin").unwrap().value(), &100);
+    /// ```
+    pub fn aggregate_into<A, L, C>(&self, leaf_fn: L, combine_fn: C) -> Tree<U, A>
+    where
+        U: Clone,
+        A: Clone,
+        L: FnMut(&Node<U, T>) -> A,
+        C: FnMut(&Node<U, T>, &[A]) -> A,
+    {
+        Tree::new(self.root.aggregate_into(leaf_fn, combine_fn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_fold_up() {
+        let tree = tree();
+        // leaves are visited left to right
+        let mut visited = Vec::new();
+        let sum = tree.fold_up(
+            |x| {
+                visited.push(*x.id());
+                *x.value()
+            },
+            |x, children| children.iter().sum::<usize>() + x.value(),
+        );
+        assert_eq!(sum, 21);
+        assert_eq!(visited, vec!["/bin/ls", "/bin/pwd", "/tmp/a/b"]);
+        // height of the tree
+        let height = tree.fold_up(|_| 1, |_, x| x.iter().max().unwrap() + 1);
+        assert_eq!(height, tree.root().depth());
+    }
+
+    #[test]
+    fn test_should_aggregate_into_tree() {
+        let leaves = tree().aggregate_into(|_| 1, |_, x| x.iter().sum::<usize>());
+        let values: Vec<(&str, usize)> = leaves
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| (*x.id(), *x.value()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("/", 3),
+                ("/bin", 2),
+                ("/bin/ls", 1),
+                ("/bin/pwd", 1),
+                ("/tmp", 1),
+                ("/tmp/a", 1),
+                ("/tmp/a/b", 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! # Binary
+//!
+//! Compact versioned binary encoding of trees.
+//!
+//! The encoding starts with a header made of the `OTRE` magic number, the format version (`u8`)
+//! and the node count (`u64`); then nodes follow in pre-order, each one made of
+//! the length-prefixed id, the length-prefixed value and the number of children.
+//! Lengths and counts are little endian `u32`.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::{Node, Tree};
+
+/// Magic number at the beginning of the encoding
+const MAGIC: &[u8; 4] = b"OTRE";
+/// Current version of the format
+const VERSION: u8 = 1;
+
+/// Error returned by a [`BinaryCodec`] which failed to decode data
+pub type DecodeError = Box<dyn Error + Send + Sync>;
+
+/// Encodes and decodes node ids or values to and from bytes
+pub trait BinaryCodec<X> {
+    /// Encode `value` into bytes
+    fn encode(&self, value: &X) -> Vec<u8>;
+
+    /// Decode a value from `bytes`
+    fn decode(&self, bytes: &[u8]) -> Result<X, DecodeError>;
+}
+
+/// [`BinaryCodec`] for UTF-8 strings
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StringCodec;
+
+impl BinaryCodec<String> for StringCodec {
+    fn encode(&self, value: &String) -> Vec<u8> {
+        value.as_bytes().to_vec()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<String, DecodeError> {
+        Ok(String::from_utf8(bytes.to_vec())?)
+    }
+}
+
+/// [`BinaryCodec`] for raw bytes
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BytesCodec;
+
+impl BinaryCodec<Vec<u8>> for BytesCodec {
+    fn encode(&self, value: &Vec<u8>) -> Vec<u8> {
+        value.clone()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// [`BinaryCodec`] for numbers, encoded as little endian
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NumberCodec;
+
+macro_rules! number_codec {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl BinaryCodec<$t> for NumberCodec {
+                fn encode(&self, value: &$t) -> Vec<u8> {
+                    value.to_le_bytes().to_vec()
+                }
+
+                fn decode(&self, bytes: &[u8]) -> Result<$t, DecodeError> {
+                    Ok(<$t>::from_le_bytes(bytes.try_into()?))
+                }
+            }
+        )*
+    };
+}
+
+number_codec!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// Describes an error which occurred while reading or writing the binary encoding
+#[derive(Debug)]
+pub enum BinaryError {
+    /// IO error
+    Io(io::Error),
+    /// The input ended before the tree was complete
+    Truncated,
+    /// The input doesn't start with the magic number
+    BadMagic,
+    /// The input has been encoded with an unsupported format version
+    UnsupportedVersion(u8),
+    /// The input is not consistent with the header
+    Corrupt(String),
+    /// The codec failed to decode the id or the value of the node at this pre-order position
+    Decode { node: u64, source: DecodeError },
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Truncated => write!(f, "truncated input"),
+            Self::BadMagic => write!(f, "bad magic number"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {version}"),
+            Self::Corrupt(reason) => write!(f, "corrupt input: {reason}"),
+            Self::Decode { node, source } => write!(f, "could not decode node {node}: {source}"),
+        }
+    }
+}
+
+impl Error for BinaryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BinaryError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Write the [`Tree`] in the binary encoding to `writer`, encoding ids and values with the provided codecs
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, NumberCodec, StringCodec, Tree};
+    ///
+    /// let tree: Tree<String, u32> = Tree::new(
+    ///     Node::new("/".to_string(), 0).with_child(Node::new("/bin".to_string(), 1)),
+    /// );
+    /// let mut bytes = Vec::new();
+    /// tree.write_binary(&mut bytes, &StringCodec, &NumberCodec).unwrap();
+    /// let decoded = Tree::read_binary(bytes.as_slice(), &StringCodec, &NumberCodec).unwrap();
+    /// assert_eq!(tree, decoded);
+    /// ```
+    pub fn write_binary<W, I, V>(
+        &self,
+        mut writer: W,
+        id_codec: &I,
+        value_codec: &V,
+    ) -> Result<(), BinaryError>
+    where
+        W: Write,
+        I: BinaryCodec<U>,
+        V: BinaryCodec<T>,
+    {
+        /// Private recursive call for write_binary
+        fn write_binary_r<U, T, W, I, V>(
+            node: &Node<U, T>,
+            writer: &mut W,
+            id_codec: &I,
+            value_codec: &V,
+        ) -> Result<(), BinaryError>
+        where
+            W: Write,
+            I: BinaryCodec<U>,
+            V: BinaryCodec<T>,
+        {
+            write_bytes(writer, &id_codec.encode(&node.id))?;
+            write_bytes(writer, &value_codec.encode(&node.value))?;
+            write_length(writer, node.children.len())?;
+            node.children
+                .iter()
+                .try_for_each(|x| write_binary_r(x, writer, id_codec, value_codec))
+        }
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&(self.root().count() as u64).to_le_bytes())?;
+        write_binary_r(self.root(), &mut writer, id_codec, value_codec)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a [`Tree`] in the binary encoding from `reader`, decoding ids and values with the provided codecs.
+    ///
+    /// Only the bytes of the tree are consumed from `reader`.
+    pub fn read_binary<R, I, V>(
+        mut reader: R,
+        id_codec: &I,
+        value_codec: &V,
+    ) -> Result<Self, BinaryError>
+    where
+        R: Read,
+        I: BinaryCodec<U>,
+        V: BinaryCodec<T>,
+    {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(BinaryError::BadMagic);
+        }
+        let mut version = [0; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(BinaryError::UnsupportedVersion(version[0]));
+        }
+        let mut count = [0; 8];
+        reader.read_exact(&mut count)?;
+        let count = u64::from_le_bytes(count);
+        // read nodes in pre-order, keeping the open nodes with the number of children left to read
+        let mut stack: Vec<(Node<U, T>, u32)> = Vec::new();
+        let mut read = 0;
+        loop {
+            match stack.last_mut() {
+                Some((_, 0)) => {
+                    let (node, _) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((parent, _)) => {
+                            if parent.children.iter().any(|x| x.id == node.id) {
+                                return Err(BinaryError::Corrupt(String::from(
+                                    "siblings with the same id",
+                                )));
+                            }
+                            parent.children.push(node);
+                        }
+                        None if read == count => return Ok(Tree::new(node)),
+                        None => {
+                            return Err(BinaryError::Corrupt(format!(
+                                "header declares {count} nodes, but found {read}"
+                            )))
+                        }
+                    }
+                    continue;
+                }
+                Some((_, children)) => *children -= 1,
+                None => {}
+            }
+            if read == count {
+                return Err(BinaryError::Corrupt(format!(
+                    "header declares {count} nodes, but found more"
+                )));
+            }
+            let id = id_codec
+                .decode(&read_bytes(&mut reader)?)
+                .map_err(|source| BinaryError::Decode { node: read, source })?;
+            let value = value_codec
+                .decode(&read_bytes(&mut reader)?)
+                .map_err(|source| BinaryError::Decode { node: read, source })?;
+            let children = read_u32(&mut reader)?;
+            stack.push((Node::new(id, value), children));
+            read += 1;
+        }
+    }
+
+    /// Encode the [`Tree`] into a buffer, encoding ids and values with the provided codecs.
+    /// Fails if an encoded id or value, or the number of children of a node, exceeds `u32::MAX`
+    pub fn to_binary<I, V>(&self, id_codec: &I, value_codec: &V) -> Result<Vec<u8>, BinaryError>
+    where
+        I: BinaryCodec<U>,
+        V: BinaryCodec<T>,
+    {
+        let mut bytes = Vec::new();
+        self.write_binary(&mut bytes, id_codec, value_codec)?;
+        Ok(bytes)
+    }
+}
+
+/// Write a length as little endian `u32`
+fn write_length<W: Write>(writer: &mut W, length: usize) -> Result<(), BinaryError> {
+    let length = u32::try_from(length).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "length exceeds the maximum of u32",
+        )
+    })?;
+    writer.write_all(&length.to_le_bytes())?;
+    Ok(())
+}
+
+/// Write length-prefixed bytes
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), BinaryError> {
+    write_length(writer, bytes.len())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, BinaryError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Read length-prefixed bytes
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, BinaryError> {
+    let length = read_u32(reader)? as u64;
+    // don't trust the length to allocate the buffer
+    let mut bytes = Vec::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(BinaryError::Truncated);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures;
+
+    fn tree() -> Tree<String, u32> {
+        fixtures::tree()
+            .map_ids(String::from)
+            .map_values(|x| x as u32)
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_binary() {
+        let tree = tree();
+        let bytes = tree.to_binary(&StringCodec, &NumberCodec).unwrap();
+        assert_eq!(&bytes[0..4], b"OTRE");
+        assert_eq!(bytes[4], 1);
+        assert_eq!(&bytes[5..13], &7u64.to_le_bytes());
+        // first node
+        assert_eq!(&bytes[13..17], &1u32.to_le_bytes());
+        assert_eq!(bytes[17], b'/');
+        // read back leaving the following data in the stream
+        let mut stream = bytes.clone();
+        stream.extend_from_slice(b"next");
+        let mut reader = stream.as_slice();
+        let decoded: Tree<String, u32> =
+            Tree::read_binary(&mut reader, &StringCodec, &NumberCodec).unwrap();
+        assert_eq!(decoded, tree);
+        assert_eq!(reader, b"next");
+    }
+
+    #[test]
+    fn test_should_report_binary_errors() {
+        let bytes = tree().to_binary(&StringCodec, &NumberCodec).unwrap();
+        let read = |bytes: &[u8]| -> Result<Tree<String, u32>, BinaryError> {
+            Tree::read_binary(bytes, &StringCodec, &NumberCodec)
+        };
+        // truncated at any point
+        for i in 0..bytes.len() {
+            assert!(read(&bytes[..i]).is_err());
+        }
+        assert!(matches!(
+            read(&bytes[..bytes.len() - 1]).unwrap_err(),
+            BinaryError::Truncated
+        ));
+        assert!(matches!(read(b"ABCD").unwrap_err(), BinaryError::BadMagic));
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 2;
+        assert!(matches!(
+            read(&corrupt).unwrap_err(),
+            BinaryError::UnsupportedVersion(2)
+        ));
+        let mut corrupt = bytes.clone();
+        corrupt[5] = 6;
+        assert!(matches!(
+            read(&corrupt).unwrap_err(),
+            BinaryError::Corrupt(_)
+        ));
+        let mut corrupt = bytes.clone();
+        corrupt[5] = 8;
+        assert!(matches!(
+            read(&corrupt).unwrap_err(),
+            BinaryError::Corrupt(_)
+        ));
+        // invalid value length for u32
+        let mut corrupt = bytes.clone();
+        corrupt[18] = 3;
+        corrupt.remove(22);
+        assert!(matches!(
+            read(&corrupt).unwrap_err(),
+            BinaryError::Decode { node: 0, .. }
+        ));
+        // siblings with the same id
+        let mut corrupt = Vec::new();
+        corrupt.extend_from_slice(MAGIC);
+        corrupt.push(VERSION);
+        corrupt.extend_from_slice(&3u64.to_le_bytes());
+        for (id, children) in [("r", 2), ("a", 0), ("a", 0)] {
+            write_bytes(&mut corrupt, id.as_bytes()).unwrap();
+            write_bytes(&mut corrupt, &0u32.to_le_bytes()).unwrap();
+            write_length(&mut corrupt, children).unwrap();
+        }
+        assert_eq!(
+            read(&corrupt).unwrap_err().to_string(),
+            BinaryError::Corrupt(String::from("siblings with the same id")).to_string()
+        );
+    }
+}
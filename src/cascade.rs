@@ -0,0 +1,179 @@
+//! # Cascade
+//!
+//! Resolve values inherited from ancestors, top-down
+
+use crate::{Node, Tree};
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Resolve the value of the node `id` with `f`, which receives the values along its ancestry,
+    /// from this node to the node `id` itself. Returns `None` if the node doesn't exist
+    pub fn resolve<R, F>(&self, id: &U, f: F) -> Option<R>
+    where
+        F: FnOnce(&[&T]) -> R,
+    {
+        let route = self.route_by_node(id)?;
+        let mut node = self;
+        let mut values = Vec::with_capacity(route.len() + 1);
+        values.push(&node.value);
+        for i in route {
+            node = &node.children[i];
+            values.push(&node.value);
+        }
+        Some(f(&values))
+    }
+
+    /// Returns a new node where each value is computed by `f` from the value of the node
+    /// and the computed value of its parent, which is `None` for this node. Nodes are visited in pre-order
+    pub fn cascade<R, F>(&self, mut f: F) -> Node<U, R>
+    where
+        U: Clone,
+        F: FnMut(&T, Option<&R>) -> R,
+    {
+        /// Private recursive call for cascade
+        fn cascade_r<U: Clone, T, R, F: FnMut(&T, Option<&R>) -> R>(
+            node: &Node<U, T>,
+            parent: Option<&R>,
+            f: &mut F,
+        ) -> Node<U, R> {
+            let value = f(&node.value, parent);
+            let children = node
+                .children
+                .iter()
+                .map(|x| cascade_r(x, Some(&value), f))
+                .collect();
+            Node {
+                id: node.id.clone(),
+                value,
+                children,
+            }
+        }
+        cascade_r(self, None, &mut f)
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Resolve the value of the node `id` from the values of its ancestry, starting from the root.
+    /// See [`Node::resolve`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// // settings where `None` is inherited from the parent
+    /// let tree: Tree<&str, Option<&str>> = Tree::new(
+    ///     Node::new("body", Some("serif"))
+    ///         .with_child(Node::new("pre", Some("mono")).with_child(Node::new("b", None)))
+    ///         .with_child(Node::new("p", None)),
+    /// );
+    /// let font = |values: &[&Option<&'static str>]| values.iter().rev().find_map(|x| **x);
+    /// assert_eq!(tree.resolve(&"b", font), Some(Some("mono")));
+    /// assert_eq!(tree.resolve(&"p", font), Some(Some("serif")));
+    /// assert_eq!(tree.resolve(&"h1", font), None);
+    /// ```
+    pub fn resolve<R, F>(&self, id: &U, f: F) -> Option<R>
+    where
+        F: FnOnce(&[&T]) -> R,
+    {
+        self.root.resolve(id, f)
+    }
+
+    /// Returns a new [`Tree`] where each value is computed from the value of the node and the computed
+    /// value of its parent. See [`Node::cascade`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let tree: Tree<&str, Option<&str>> = Tree::new(
+    ///     Node::new("body", Some("serif"))
+    ///         .with_child(Node::new("pre", Some("mono")).with_child(Node::new("b", None)))
+    ///         .with_child(Node::new("p", None)),
+    /// );
+    /// let fonts = tree.cascade(|value, parent| value.or(parent.copied().flatten()));
+    /// assert_eq!(fonts.root().query(&"b").unwrap().value(), &Some("mono"));
+    /// assert_eq!(fonts.root().query(&"p").unwrap().value(), &Some("serif"));
+    /// ```
+    pub fn cascade<R, F>(&self, f: F) -> Tree<U, R>
+    where
+        U: Clone,
+        F: FnMut(&T, Option<&R>) -> R,
+    {
+        Tree::new(self.root.cascade(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    type Settings = HashMap<&'static str, &'static str>;
+
+    fn settings(pairs: &[(&'static str, &'static str)]) -> Settings {
+        pairs.iter().copied().collect()
+    }
+
+    fn tree() -> Tree<&'static str, Settings> {
+        Tree::new(
+            Node::new("root", settings(&[("color", "black"), ("size", "12")]))
+                .with_child(
+                    Node::new("header", settings(&[("size", "20")]))
+                        .with_child(Node::new("title", settings(&[("color", "red")]))),
+                )
+                .with_child(Node::new("footer", settings(&[]))),
+        )
+    }
+
+    #[test]
+    fn test_should_resolve_node_from_ancestors() {
+        let tree = tree();
+        let merge = |values: &[&Settings]| {
+            values.iter().fold(Settings::new(), |mut acc, x| {
+                acc.extend(x.iter());
+                acc
+            })
+        };
+        assert_eq!(
+            tree.resolve(&"title", merge).unwrap(),
+            settings(&[("color", "red"), ("size", "20")])
+        );
+        assert_eq!(
+            tree.resolve(&"footer", merge).unwrap(),
+            settings(&[("color", "black"), ("size", "12")])
+        );
+        assert_eq!(tree.resolve(&"root", |x| x.len()), Some(1));
+        assert_eq!(tree.resolve(&"title", |x| x.len()), Some(3));
+        assert!(tree.resolve(&"body", merge).is_none());
+    }
+
+    #[test]
+    fn test_should_cascade_values() {
+        let tree = tree();
+        let mut visited = Vec::new();
+        let resolved = tree.cascade(|value, parent: Option<&Settings>| {
+            visited.push(value.len());
+            let mut resolved = parent.cloned().unwrap_or_default();
+            resolved.extend(value.iter());
+            resolved
+        });
+        assert_eq!(visited, vec![2, 1, 1, 0]);
+        assert_eq!(
+            resolved.root().query(&"title").unwrap().value(),
+            &settings(&[("color", "red"), ("size", "20")])
+        );
+        assert_eq!(
+            resolved.root().query(&"footer").unwrap().value(),
+            tree.root().value()
+        );
+        // cascade agrees with resolve
+        let depth = tree.cascade(|_, parent| parent.map(|x| x + 1).unwrap_or(0));
+        for id in ["root", "header", "title", "footer"] {
+            assert_eq!(
+                depth.root().query(&id).unwrap().value() + 1,
+                tree.resolve(&id, |x| x.len()).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! # Diff
+//!
+//! Compare two snapshots of a tree, keyed on node ids, producing an edit script
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::{Node, Tree};
+
+/// An operation of the edit script returned by [`diff`].
+///
+/// Routes of removed nodes refer to the old tree, while all the other routes refer to the new tree
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edit<U, T> {
+    /// Insert the node `id`, without children, as child of `parent` at `index`
+    Insert {
+        id: U,
+        parent: U,
+        index: usize,
+        value: T,
+        route: Vec<usize>,
+    },
+    /// Remove the node `id`, with its subtree, from the children of `parent`
+    Remove { id: U, parent: U, route: Vec<usize> },
+    /// Move the node `id`, with its subtree, from the children of `from_parent` to the children of `to_parent` at `index`
+    Move {
+        id: U,
+        from_parent: U,
+        to_parent: U,
+        index: usize,
+        route: Vec<usize>,
+    },
+    /// Sort the children of `parent` as `order`
+    Reorder {
+        parent: U,
+        order: Vec<U>,
+        route: Vec<usize>,
+    },
+    /// Change the value of the node `id`
+    UpdateValue {
+        id: U,
+        old: T,
+        new: T,
+        route: Vec<usize>,
+    },
+}
+
+/// Describes why two trees can't be compared
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiffError<U> {
+    /// The roots of the trees have different ids
+    RootMismatch,
+    /// The id is used by more than one node of the same tree
+    DuplicateId(U),
+}
+
+impl<U: fmt::Debug> fmt::Display for DiffError<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RootMismatch => write!(f, "the roots have different ids"),
+            Self::DuplicateId(id) => write!(f, "duplicate node {id:?}"),
+        }
+    }
+}
+
+impl<U: fmt::Debug> std::error::Error for DiffError<U> {}
+
+/// A node indexed by id, with its parent and route
+pub(crate) struct Indexed<'a, U, T> {
+    pub node: &'a Node<U, T>,
+    pub parent: Option<&'a U>,
+    pub route: Vec<usize>,
+}
+
+/// The nodes of a tree indexed by id
+pub(crate) struct Index<'a, U, T> {
+    pub nodes: HashMap<&'a U, Indexed<'a, U, T>>,
+    /// Ids in pre-order
+    pub order: Vec<&'a U>,
+}
+
+/// Index the nodes of `tree` by id
+pub(crate) fn index<U, T>(tree: &Tree<U, T>) -> Result<Index<'_, U, T>, DiffError<U>>
+where
+    U: Eq + Hash + Clone,
+{
+    let mut nodes = HashMap::new();
+    let mut order = Vec::new();
+    let mut stack = vec![(tree.root(), None, Vec::new())];
+    while let Some((node, parent, route)) = stack.pop() {
+        for (i, child) in node.children.iter().enumerate().rev() {
+            let mut route = route.clone();
+            route.push(i);
+            stack.push((child, Some(&node.id), route));
+        }
+        if nodes.contains_key(&node.id) {
+            return Err(DiffError::DuplicateId(node.id.clone()));
+        }
+        order.push(&node.id);
+        nodes.insert(
+            &node.id,
+            Indexed {
+                node,
+                parent,
+                route,
+            },
+        );
+    }
+    Ok(Index { nodes, order })
+}
+
+/// Compare `old` with `new`, keyed on node ids, returning the edit script which turns `old` into `new`.
+///
+/// Edits are sorted so that they can be applied in sequence: inserts and moves in pre-order of the new tree,
+/// then removals of the topmost removed nodes, then reorders of the children which are still out of order,
+/// and finally value updates. Indexes of inserts and moves are the positions in the new tree;
+/// they may exceed the current number of children while applying, in which case the node is appended.
+///
+/// ```rust
+/// use orange_trees::{diff, Edit, Node, Tree};
+///
+/// let old: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 1)));
+/// let new: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 2)));
+/// assert_eq!(
+///     diff(&old, &new).unwrap(),
+///     vec![Edit::UpdateValue { id: "/a", old: 1, new: 2, route: vec![0] }]
+/// );
+/// ```
+pub fn diff<U, T>(old: &Tree<U, T>, new: &Tree<U, T>) -> Result<Vec<Edit<U, T>>, DiffError<U>>
+where
+    U: Eq + Hash + Clone,
+    T: PartialEq + Clone,
+{
+    if old.root().id != new.root().id {
+        return Err(DiffError::RootMismatch);
+    }
+    let Index {
+        nodes: old_nodes,
+        order: old_order,
+    } = index(old)?;
+    let Index {
+        nodes: new_nodes,
+        order: new_order,
+    } = index(new)?;
+    // children lists, updated while edits are computed
+    let mut children: HashMap<&U, Vec<&U>> = old_nodes
+        .iter()
+        .map(|(id, x)| (*id, x.node.children.iter().map(|x| &x.id).collect()))
+        .collect();
+    let mut edits = Vec::new();
+    // inserts and moves
+    for id in new_order.iter().skip(1) {
+        let indexed = &new_nodes[id];
+        let parent = indexed.parent.unwrap();
+        let index = *indexed.route.last().unwrap();
+        let from_parent = match old_nodes.get(id) {
+            None => {
+                edits.push(Edit::Insert {
+                    id: (*id).clone(),
+                    parent: parent.clone(),
+                    index,
+                    value: indexed.node.value.clone(),
+                    route: indexed.route.clone(),
+                });
+                children.insert(id, Vec::new());
+                None
+            }
+            Some(x) if x.parent != Some(parent) => {
+                let from_parent = x.parent.unwrap();
+                edits.push(Edit::Move {
+                    id: (*id).clone(),
+                    from_parent: from_parent.clone(),
+                    to_parent: parent.clone(),
+                    index,
+                    route: indexed.route.clone(),
+                });
+                Some(from_parent)
+            }
+            Some(_) => continue,
+        };
+        if let Some(from_parent) = from_parent {
+            children.get_mut(from_parent).unwrap().retain(|x| x != id);
+        }
+        let siblings = children.get_mut(parent).unwrap();
+        siblings.insert(index.min(siblings.len()), id);
+    }
+    // removals
+    for id in old_order.iter().skip(1) {
+        let parent = old_nodes[id].parent.unwrap();
+        if !new_nodes.contains_key(id) && new_nodes.contains_key(parent) {
+            edits.push(Edit::Remove {
+                id: (*id).clone(),
+                parent: parent.clone(),
+                route: old_nodes[id].route.clone(),
+            });
+            children.get_mut(parent).unwrap().retain(|x| x != id);
+        }
+    }
+    // reorders
+    for id in new_order.iter() {
+        let indexed = &new_nodes[id];
+        let order: Vec<&U> = indexed.node.children.iter().map(|x| &x.id).collect();
+        if !order.is_empty() && children[id] != order {
+            edits.push(Edit::Reorder {
+                parent: (*id).clone(),
+                order: order.into_iter().cloned().collect(),
+                route: indexed.route.clone(),
+            });
+        }
+    }
+    // value updates
+    for id in new_order.iter() {
+        let indexed = &new_nodes[id];
+        if let Some(previous) = old_nodes.get(id) {
+            if previous.node.value != indexed.node.value {
+                edits.push(Edit::UpdateValue {
+                    id: (*id).clone(),
+                    old: previous.node.value.clone(),
+                    new: indexed.node.value.clone(),
+                    route: indexed.route.clone(),
+                });
+            }
+        }
+    }
+    Ok(edits)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_diff_equal_trees() {
+        assert_eq!(diff(&tree(), &tree()).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_should_diff_trees() {
+        let old = tree();
+        let new: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/c", 7).with_child(Node::new("/tmp/c/d", 8))),
+                )
+                .with_child(
+                    Node::new("/bin", 10)
+                        .with_child(Node::new("/bin/pwd", 3))
+                        .with_child(Node::new("/tmp/a/b", 6))
+                        .with_child(Node::new("/bin/ls", 2)),
+                ),
+        );
+        assert_eq!(
+            diff(&old, &new).unwrap(),
+            vec![
+                Edit::Insert {
+                    id: "/tmp/c",
+                    parent: "/tmp",
+                    index: 0,
+                    value: 7,
+                    route: vec![0, 0]
+                },
+                Edit::Insert {
+                    id: "/tmp/c/d",
+                    parent: "/tmp/c",
+                    index: 0,
+                    value: 8,
+                    route: vec![0, 0, 0]
+                },
+                Edit::Move {
+                    id: "/tmp/a/b",
+                    from_parent: "/tmp/a",
+                    to_parent: "/bin",
+                    index: 1,
+                    route: vec![1, 1]
+                },
+                Edit::Remove {
+                    id: "/tmp/a",
+                    parent: "/tmp",
+                    route: vec![1, 0]
+                },
+                Edit::Reorder {
+                    parent: "/",
+                    order: vec!["/tmp", "/bin"],
+                    route: vec![]
+                },
+                Edit::Reorder {
+                    parent: "/bin",
+                    order: vec!["/bin/pwd", "/tmp/a/b", "/bin/ls"],
+                    route: vec![1]
+                },
+                Edit::UpdateValue {
+                    id: "/bin",
+                    old: 1,
+                    new: 10,
+                    route: vec![1]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_report_diff_errors() {
+        let other: Tree<&'static str, usize> = Tree::new(Node::new("root", 0));
+        assert_eq!(diff(&tree(), &other).unwrap_err(), DiffError::RootMismatch);
+        let duplicated: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0).with_child(Node::new("/a", 1).with_child(Node::new("/a", 2))),
+        );
+        assert_eq!(
+            diff(&tree(), &duplicated).unwrap_err(),
+            DiffError::DuplicateId("/a")
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! # Edit distance
+//!
+//! Ordered tree edit distance (Zhang–Shasha), comparing nodes by their content rather than by id
+
+use crate::{Node, Tree};
+
+/// The result of [`Node::edit_distance`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditDistance {
+    /// Minimum cost to turn the first tree into the second one
+    pub distance: f64,
+    /// Pairs of routes of the nodes mapped onto each other (kept or relabelled), sorted by the first route.
+    /// Nodes of the first tree which don't appear are deleted, while the ones of the second tree are inserted
+    pub mapping: Vec<(Vec<usize>, Vec<usize>)>,
+}
+
+/// Nodes of a tree in post-order, where indexes start from `1`
+struct Postorder<'a, U, T> {
+    nodes: Vec<&'a Node<U, T>>,
+    routes: Vec<Vec<usize>>,
+    /// Index of the leftmost leaf descendant of each node
+    lmd: Vec<usize>,
+    keyroots: Vec<usize>,
+}
+
+impl<'a, U, T> Postorder<'a, U, T> {
+    fn new(root: &'a Node<U, T>) -> Self {
+        /// Private recursive call for new
+        fn postorder_r<'a, U, T>(
+            node: &'a Node<U, T>,
+            route: &mut Vec<usize>,
+            postorder: &mut Postorder<'a, U, T>,
+        ) -> usize {
+            let mut lmd = None;
+            for (i, child) in node.children.iter().enumerate() {
+                route.push(i);
+                let child_lmd = postorder_r(child, route, postorder);
+                lmd.get_or_insert(child_lmd);
+                route.pop();
+            }
+            postorder.nodes.push(node);
+            postorder.routes.push(route.clone());
+            let lmd = lmd.unwrap_or(postorder.nodes.len() - 1);
+            postorder.lmd.push(lmd);
+            lmd
+        }
+        // index 0 is a placeholder
+        let mut postorder = Self {
+            nodes: vec![root],
+            routes: vec![Vec::new()],
+            lmd: vec![0],
+            keyroots: Vec::new(),
+        };
+        postorder_r(root, &mut Vec::new(), &mut postorder);
+        // keyroots are the highest nodes with a given leftmost leaf descendant
+        let n = postorder.nodes.len() - 1;
+        let mut seen = vec![false; n + 1];
+        for i in (1..=n).rev() {
+            if !seen[postorder.lmd[i]] {
+                seen[postorder.lmd[i]] = true;
+                postorder.keyroots.push(i);
+            }
+        }
+        postorder.keyroots.reverse();
+        postorder
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+}
+
+/// Cost functions and trees being compared
+struct Costs<'a, U, T, I, D, R> {
+    a: Postorder<'a, U, T>,
+    b: Postorder<'a, U, T>,
+    insert: I,
+    delete: D,
+    relabel: R,
+    /// Distance between the subtrees rooted at each pair of nodes
+    td: Vec<Vec<f64>>,
+}
+
+impl<U, T, I, D, R> Costs<'_, U, T, I, D, R>
+where
+    I: Fn(&Node<U, T>) -> f64,
+    D: Fn(&Node<U, T>) -> f64,
+    R: Fn(&Node<U, T>, &Node<U, T>) -> f64,
+{
+    fn delete(&self, x: usize) -> f64 {
+        (self.delete)(self.a.nodes[x])
+    }
+
+    fn insert(&self, y: usize) -> f64 {
+        (self.insert)(self.b.nodes[y])
+    }
+
+    /// Compute the distances between the forests of the subtrees rooted at `i` and `j`,
+    /// updating the distances between subtrees
+    fn forest_distance(&mut self, i: usize, j: usize) -> Vec<Vec<f64>> {
+        let (li, lj) = (self.a.lmd[i], self.b.lmd[j]);
+        let mut fd = vec![vec![0.0; j - lj + 2]; i - li + 2];
+        for x in li..=i {
+            fd[x - li + 1][0] = fd[x - li][0] + self.delete(x);
+        }
+        for y in lj..=j {
+            fd[0][y - lj + 1] = fd[0][y - lj] + self.insert(y);
+        }
+        for x in li..=i {
+            for y in lj..=j {
+                let (xi, yi) = (x - li + 1, y - lj + 1);
+                let delete = fd[xi - 1][yi] + self.delete(x);
+                let insert = fd[xi][yi - 1] + self.insert(y);
+                if self.a.lmd[x] == li && self.b.lmd[y] == lj {
+                    let relabel =
+                        fd[xi - 1][yi - 1] + (self.relabel)(self.a.nodes[x], self.b.nodes[y]);
+                    fd[xi][yi] = delete.min(insert).min(relabel);
+                    self.td[x][y] = fd[xi][yi];
+                } else {
+                    let subtree = fd[self.a.lmd[x] - li][self.b.lmd[y] - lj] + self.td[x][y];
+                    fd[xi][yi] = delete.min(insert).min(subtree);
+                }
+            }
+        }
+        fd
+    }
+
+    /// Backtrack the forest distances to find the mapping of the optimal edit script
+    fn mapping(&mut self) -> Vec<(Vec<usize>, Vec<usize>)> {
+        let mut mapping = Vec::new();
+        let mut stack = vec![(self.a.len(), self.b.len())];
+        while let Some((i, j)) = stack.pop() {
+            let fd = self.forest_distance(i, j);
+            let (li, lj) = (self.a.lmd[i], self.b.lmd[j]);
+            let (mut x, mut y) = (i, j);
+            while x >= li || y >= lj {
+                let (xi, yi) = (x + 1 - li, y + 1 - lj);
+                if xi > 0 && fd[xi][yi] == fd[xi - 1][yi] + self.delete(x) {
+                    x -= 1;
+                } else if yi > 0 && fd[xi][yi] == fd[xi][yi - 1] + self.insert(y) {
+                    y -= 1;
+                } else if self.a.lmd[x] == li && self.b.lmd[y] == lj {
+                    mapping.push((self.a.routes[x].clone(), self.b.routes[y].clone()));
+                    x -= 1;
+                    y -= 1;
+                } else {
+                    stack.push((x, y));
+                    x = self.a.lmd[x] - 1;
+                    y = self.b.lmd[y] - 1;
+                }
+            }
+        }
+        mapping.sort();
+        mapping
+    }
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Compute the ordered tree edit distance between this [`Node`] and `other` with the Zhang–Shasha algorithm,
+    /// ignoring ids unless the cost functions use them.
+    ///
+    /// `insert` and `delete` return the cost of inserting a node of `other` and of deleting a node of this tree,
+    /// while `relabel` returns the cost of turning a node of this tree into one of `other`, which should be `0`
+    /// for equal nodes. Costs must be non-negative. Children of the nodes passed to cost functions are
+    /// the original ones.
+    ///
+    /// ```rust
+    /// use orange_trees::Node;
+    ///
+    /// let a: Node<usize, &str> = Node::new(0, "f").with_child(Node::new(1, "a")).with_child(Node::new(2, "b"));
+    /// let b: Node<usize, &str> = Node::new(7, "f").with_child(Node::new(8, "b"));
+    /// let result = a.edit_distance(&b, |_| 1.0, |_| 1.0, |x, y| if x.value() == y.value() { 0.0 } else { 1.0 });
+    /// assert_eq!(result.distance, 1.0);
+    /// assert_eq!(result.mapping, vec![(vec![], vec![]), (vec![1], vec![0])]);
+    /// ```
+    pub fn edit_distance<I, D, R>(
+        &self,
+        other: &Self,
+        insert: I,
+        delete: D,
+        relabel: R,
+    ) -> EditDistance
+    where
+        I: Fn(&Self) -> f64,
+        D: Fn(&Self) -> f64,
+        R: Fn(&Self, &Self) -> f64,
+    {
+        let a = Postorder::new(self);
+        let b = Postorder::new(other);
+        let td = vec![vec![0.0; b.len() + 1]; a.len() + 1];
+        let mut costs = Costs {
+            a,
+            b,
+            insert,
+            delete,
+            relabel,
+            td,
+        };
+        for i in costs.a.keyroots.clone() {
+            for j in costs.b.keyroots.clone() {
+                costs.forest_distance(i, j);
+            }
+        }
+        EditDistance {
+            distance: costs.td[costs.a.len()][costs.b.len()],
+            mapping: costs.mapping(),
+        }
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Compute the ordered tree edit distance between this [`Tree`] and `other`. See [`Node::edit_distance`]
+    pub fn edit_distance<I, D, R>(
+        &self,
+        other: &Self,
+        insert: I,
+        delete: D,
+        relabel: R,
+    ) -> EditDistance
+    where
+        I: Fn(&Node<U, T>) -> f64,
+        D: Fn(&Node<U, T>) -> f64,
+        R: Fn(&Node<U, T>, &Node<U, T>) -> f64,
+    {
+        self.root
+            .edit_distance(other.root(), insert, delete, relabel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Build a node labelled `value`, using a generated id
+    fn node(value: char, children: Vec<Node<usize, char>>) -> Node<usize, char> {
+        static ID: AtomicUsize = AtomicUsize::new(0);
+        Node::new(ID.fetch_add(1, Ordering::Relaxed), value).with_children(children)
+    }
+
+    fn unit(a: &Tree<usize, char>, b: &Tree<usize, char>) -> EditDistance {
+        a.edit_distance(
+            b,
+            |_| 1.0,
+            |_| 1.0,
+            |x, y| if x.value() == y.value() { 0.0 } else { 1.0 },
+        )
+    }
+
+    #[test]
+    fn test_should_compute_edit_distance() {
+        // example from Zhang and Shasha's paper
+        let a = Tree::new(node(
+            'f',
+            vec![
+                node(
+                    'd',
+                    vec![node('a', vec![]), node('c', vec![node('b', vec![])])],
+                ),
+                node('e', vec![]),
+            ],
+        ));
+        let b = Tree::new(node(
+            'f',
+            vec![
+                node(
+                    'c',
+                    vec![node('d', vec![node('a', vec![]), node('b', vec![])])],
+                ),
+                node('e', vec![]),
+            ],
+        ));
+        let result = unit(&a, &b);
+        assert_eq!(result.distance, 2.0);
+        // c is deleted and inserted again above d
+        assert_eq!(
+            result.mapping,
+            vec![
+                (vec![], vec![]),
+                (vec![0], vec![0, 0]),
+                (vec![0, 0], vec![0, 0, 0]),
+                (vec![0, 1, 0], vec![0, 0, 1]),
+                (vec![1], vec![1]),
+            ]
+        );
+        assert_eq!(unit(&a, &a).distance, 0.0);
+        assert_eq!(unit(&a, &a).mapping.len(), a.root().count());
+    }
+
+    #[test]
+    fn test_should_use_custom_costs() {
+        let a = Tree::new(node('r', vec![node('x', vec![]), node('y', vec![])]));
+        let b = Tree::new(node('r', vec![node('z', vec![]), node('y', vec![])]));
+        // relabelling is cheaper than deleting and inserting
+        let result = unit(&a, &b);
+        assert_eq!(result.distance, 1.0);
+        assert_eq!(result.mapping.len(), 3);
+        // relabelling is more expensive than deleting and inserting
+        let result = a.edit_distance(
+            &b,
+            |_| 1.0,
+            |_| 1.0,
+            |x, y| if x.value() == y.value() { 0.0 } else { 5.0 },
+        );
+        assert_eq!(result.distance, 2.0);
+        assert_eq!(result.mapping, vec![(vec![], vec![]), (vec![1], vec![1])]);
+        // deleting everything but the root
+        let c = Tree::new(node('r', vec![]));
+        let result = a.edit_distance(
+            &c,
+            |_| 1.0,
+            |_| 0.5,
+            |x, y| if x.value() == y.value() { 0.0 } else { 1.0 },
+        );
+        assert_eq!(result.distance, 1.0);
+        assert_eq!(result.mapping, vec![(vec![], vec![])]);
+    }
+}
@@ -0,0 +1,152 @@
+//! # Filter
+//!
+//! Prune trees by predicate, in place or into new trees
+
+use crate::{Node, Tree};
+
+/// Describes which nodes are kept by [`Node::filtered`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterMode {
+    /// Keep the nodes satisfying the predicate together with all their ancestors, so that the path
+    /// to each match is preserved
+    KeepAncestors,
+    /// Drop the nodes which don't satisfy the predicate together with their whole subtree
+    DropSubtree,
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Remove, with their subtree, all the descendants of the [`Node`] which don't satisfy `predicate`.
+    /// The predicate is not evaluated on this node, nor on the descendants of the removed nodes
+    pub fn retain_recursive<P>(&mut self, predicate: &P)
+    where
+        P: Fn(&Self) -> bool,
+    {
+        self.children.retain(|x| predicate(x));
+        self.children
+            .iter_mut()
+            .for_each(|x| x.retain_recursive(predicate));
+    }
+
+    /// Returns a copy of the [`Node`] with only the nodes selected by `predicate` according to `mode`,
+    /// or `None` if this node is not selected.
+    ///
+    /// ```rust
+    /// use orange_trees::{FilterMode, Node};
+    ///
+    /// let node: Node<&str, &str> = Node::new("/", "/")
+    ///     .with_child(
+    ///         Node::new("/bin", "bin")
+    ///             .with_child(Node::new("/bin/ls", "ls"))
+    ///             .with_child(Node::new("/bin/pwd", "pwd")),
+    ///     )
+    ///     .with_child(Node::new("/tmp", "tmp"));
+    /// let filtered = node.filtered(&|x| x.value().contains('w'), FilterMode::KeepAncestors).unwrap();
+    /// assert_eq!(filtered.count(), 3);
+    /// assert!(filtered.query(&"/bin/pwd").is_some());
+    /// assert!(node.filtered(&|x| x.value().contains('w'), FilterMode::DropSubtree).is_none());
+    /// ```
+    pub fn filtered<P>(&self, predicate: &P, mode: FilterMode) -> Option<Self>
+    where
+        U: Clone,
+        T: Clone,
+        P: Fn(&Self) -> bool,
+    {
+        let matches = predicate(self);
+        if mode == FilterMode::DropSubtree && !matches {
+            return None;
+        }
+        let children: Vec<Self> = self
+            .children
+            .iter()
+            .filter_map(|x| x.filtered(predicate, mode))
+            .collect();
+        if matches || !children.is_empty() {
+            Some(Node {
+                id: self.id.clone(),
+                value: self.value.clone(),
+                children,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Returns a copy of the [`Tree`] with only the nodes selected by `predicate` according to `mode`,
+    /// or `None` if the root is not selected. See [`Node::filtered`]
+    pub fn filtered<P>(&self, predicate: &P, mode: FilterMode) -> Option<Self>
+    where
+        U: Clone,
+        T: Clone,
+        P: Fn(&Node<U, T>) -> bool,
+    {
+        self.root.filtered(predicate, mode).map(Tree::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    fn ids(node: &Node<&'static str, usize>) -> Vec<&'static str> {
+        node.find(&|_| true).into_iter().map(|x| *x.id()).collect()
+    }
+
+    #[test]
+    fn test_should_retain_recursive() {
+        let mut tree = tree();
+        tree.root_mut().retain_recursive(&|x| x.value() % 2 == 0);
+        assert_eq!(ids(tree.root()), vec!["/", "/tmp"]);
+        let mut tree = self::tree();
+        tree.root_mut().retain_recursive(&|x| *x.value() != 5);
+        assert_eq!(
+            ids(tree.root()),
+            vec!["/", "/bin", "/bin/ls", "/bin/pwd", "/tmp"]
+        );
+    }
+
+    #[test]
+    fn test_should_filter_keeping_ancestors() {
+        let tree = tree();
+        let filtered = tree
+            .filtered(
+                &|x| x.value() % 3 == 0 && *x.value() > 0,
+                FilterMode::KeepAncestors,
+            )
+            .unwrap();
+        assert_eq!(
+            ids(filtered.root()),
+            vec!["/", "/bin", "/bin/pwd", "/tmp", "/tmp/a", "/tmp/a/b"]
+        );
+        // descendants of matches which don't match are dropped
+        let filtered = tree
+            .filtered(&|x| *x.value() == 1, FilterMode::KeepAncestors)
+            .unwrap();
+        assert_eq!(ids(filtered.root()), vec!["/", "/bin"]);
+        assert!(tree
+            .filtered(&|x| *x.value() > 10, FilterMode::KeepAncestors)
+            .is_none());
+        // the source tree is untouched
+        assert_eq!(tree.root().count(), 7);
+    }
+
+    #[test]
+    fn test_should_filter_dropping_subtrees() {
+        let tree = tree();
+        let filtered = tree
+            .filtered(&|x| *x.value() != 1, FilterMode::DropSubtree)
+            .unwrap();
+        assert_eq!(
+            ids(filtered.root()),
+            vec!["/", "/tmp", "/tmp/a", "/tmp/a/b"]
+        );
+        assert!(tree
+            .filtered(&|x| *x.value() != 0, FilterMode::DropSubtree)
+            .is_none());
+    }
+}
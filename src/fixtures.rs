@@ -0,0 +1,21 @@
+//! # Fixtures
+//!
+//! Trees shared by the tests of the modules
+
+use crate::{Node, Tree};
+
+/// A file system tree, where ids are the paths and values are numbered in pre-order
+pub(crate) fn tree() -> Tree<&'static str, usize> {
+    Tree::new(
+        Node::new("/", 0)
+            .with_child(
+                Node::new("/bin", 1)
+                    .with_child(Node::new("/bin/ls", 2))
+                    .with_child(Node::new("/bin/pwd", 3)),
+            )
+            .with_child(
+                Node::new("/tmp", 4)
+                    .with_child(Node::new("/tmp/a", 5).with_child(Node::new("/tmp/a/b", 6))),
+            ),
+    )
+}
@@ -0,0 +1,529 @@
+//! # Fs
+//!
+//! Scan directories of the filesystem into trees
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::{Node, Tree};
+
+/// Describes the kind of a filesystem entry
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FsKind {
+    File,
+    Directory,
+    /// A symbolic link which has not been followed
+    Symlink,
+    /// Any other kind of entry, such as sockets and devices
+    Other,
+}
+
+/// The value of a node scanned from the filesystem
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FsEntry {
+    /// File name of the entry; for the scanned root, the path as provided
+    pub name: String,
+    pub kind: FsKind,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub readonly: bool,
+    /// Target of the entry, if it is a symbolic link
+    pub target: Option<PathBuf>,
+}
+
+impl FsEntry {
+    /// Read the entry at `path`, following symbolic links if `follow_symlinks` is set
+    fn read(path: &Path, name: String, follow_symlinks: bool) -> io::Result<Self> {
+        let metadata = fs::symlink_metadata(path)?;
+        let target = match metadata.file_type().is_symlink() {
+            true => Some(fs::read_link(path)?),
+            false => None,
+        };
+        let metadata = match target.is_some() && follow_symlinks {
+            true => fs::metadata(path)?,
+            false => metadata,
+        };
+        Ok(Self::from_metadata(name, &metadata, target))
+    }
+
+    /// Build the entry from the metadata read from the filesystem
+    fn from_metadata(name: String, metadata: &Metadata, target: Option<PathBuf>) -> Self {
+        let file_type = metadata.file_type();
+        let kind = if file_type.is_dir() {
+            FsKind::Directory
+        } else if file_type.is_file() {
+            FsKind::File
+        } else if file_type.is_symlink() {
+            FsKind::Symlink
+        } else {
+            FsKind::Other
+        };
+        Self {
+            name,
+            kind,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            readonly: metadata.permissions().readonly(),
+            target,
+        }
+    }
+
+    /// Returns whether the entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.kind == FsKind::Directory
+    }
+}
+
+/// Options for [`Tree::scan`]
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    hidden: bool,
+    patterns: Vec<Glob>,
+    exclude: GlobSet,
+}
+
+impl ScanOptions {
+    /// Instantiates new [`ScanOptions`]: no depth limit, symbolic links not followed and hidden files skipped
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum depth to scan, where `0` means the root only
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Set whether to follow symbolic links to directories
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Set whether to include hidden entries, whose name starts with `.`
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Exclude the entries matching the glob `pattern`, with their descendants.
+    /// The pattern is matched against both the path relative to the root and the file name
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, globset::Error> {
+        self.patterns.push(Glob::new(pattern)?);
+        let mut builder = GlobSetBuilder::new();
+        self.patterns.iter().cloned().for_each(|x| {
+            builder.add(x);
+        });
+        self.exclude = builder.build()?;
+        Ok(self)
+    }
+
+    /// Returns whether the entry at `relative` with `name` must be skipped
+    fn skips(&self, relative: &Path, name: &str) -> bool {
+        (!self.hidden && name.starts_with('.'))
+            || self.exclude.is_match(relative)
+            || self.exclude.is_match(name)
+    }
+}
+
+/// An error which occurred while reading an entry; the scan goes on with the other entries
+#[derive(Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for ScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The result of [`Tree::scan`]
+#[derive(Debug)]
+pub struct FsScan {
+    pub tree: Tree<PathBuf, FsEntry>,
+    /// Errors met while scanning, such as directories which couldn't be read
+    pub errors: Vec<ScanError>,
+}
+
+/// A change found by [`Tree::rescan`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FsChange {
+    /// The entry has been added, with all its descendants
+    Added(PathBuf),
+    /// The entry has been removed, with all its descendants
+    Removed(PathBuf),
+    /// The metadata of the entry have changed
+    Modified(PathBuf),
+}
+
+/// The result of [`Tree::rescan`]
+#[derive(Debug)]
+pub struct FsRescan {
+    /// Changes applied to the tree, in pre-order
+    pub changes: Vec<FsChange>,
+    /// Errors met while scanning
+    pub errors: Vec<ScanError>,
+}
+
+impl Tree<PathBuf, FsEntry> {
+    /// Scan the directory at `root` into a [`Tree`], where each node has the path of the entry as id.
+    ///
+    /// Children are sorted by name. Entries which can't be read are reported in [`FsScan::errors`]
+    /// instead of aborting the scan; only failing to read `root` itself is an error.
+    ///
+    /// ```rust,no_run
+    /// use orange_trees::{ScanOptions, Tree};
+    ///
+    /// let options = ScanOptions::new().max_depth(2).exclude("target").unwrap();
+    /// let scan = Tree::scan(".", &options).unwrap();
+    /// for error in scan.errors.iter() {
+    ///     eprintln!("{error}");
+    /// }
+    /// println!("{} entries", scan.tree.root().count());
+    /// ```
+    pub fn scan(root: impl AsRef<Path>, options: &ScanOptions) -> io::Result<FsScan> {
+        let root = root.as_ref();
+        let entry = FsEntry::read(root, root.display().to_string(), true)?;
+        let mut errors = Vec::new();
+        let mut ancestors = Vec::new();
+        let node = scan_node(root, root, entry, 0, options, &mut ancestors, &mut errors);
+        Ok(FsScan {
+            tree: Tree::new(node),
+            errors,
+        })
+    }
+
+    /// Scan again the directory of the root, updating the [`Tree`] in place with the entries which have been
+    /// added, removed or modified (by size, modification time, kind, permissions or link target) since the last scan.
+    ///
+    /// Unchanged nodes are kept as they are. Added and removed directories are reported once, not with their descendants;
+    /// entries which can't be read anymore are reported as removed. Fails only if the root can't be read.
+    pub fn rescan(&mut self, options: &ScanOptions) -> io::Result<FsRescan> {
+        let root = self.root.id.clone();
+        let entry = FsEntry::read(&root, self.root.value.name.clone(), true)?;
+        let mut changes = Vec::new();
+        if entry != self.root.value {
+            changes.push(FsChange::Modified(root.clone()));
+            self.root.value = entry;
+        }
+        let mut errors = Vec::new();
+        let mut ancestors = Vec::new();
+        rescan_node(
+            &root,
+            &mut self.root,
+            0,
+            options,
+            &mut ancestors,
+            &mut changes,
+            &mut errors,
+        );
+        Ok(FsRescan { changes, errors })
+    }
+}
+
+/// Build the node for the entry at `path`, scanning its children if it is a directory
+fn scan_node(
+    root: &Path,
+    path: &Path,
+    entry: FsEntry,
+    depth: usize,
+    options: &ScanOptions,
+    ancestors: &mut Vec<PathBuf>,
+    errors: &mut Vec<ScanError>,
+) -> Node<PathBuf, FsEntry> {
+    let mut node = Node::new(path.to_path_buf(), entry);
+    if !expands(&node.value, depth, options) || !enter(path, ancestors, errors) {
+        return node;
+    }
+    match read_children(root, path, options, errors) {
+        Ok(children) => {
+            for (path, entry) in children {
+                let child = scan_node(root, &path, entry, depth + 1, options, ancestors, errors);
+                node.children.push(child);
+            }
+        }
+        Err(error) => errors.push(error),
+    }
+    ancestors.pop();
+    node
+}
+
+/// Reconcile the children of `node`, whose value has already been updated, with the filesystem
+fn rescan_node(
+    root: &Path,
+    node: &mut Node<PathBuf, FsEntry>,
+    depth: usize,
+    options: &ScanOptions,
+    ancestors: &mut Vec<PathBuf>,
+    changes: &mut Vec<FsChange>,
+    errors: &mut Vec<ScanError>,
+) {
+    if !expands(&node.value, depth, options) {
+        changes.extend(node.children.drain(..).map(|x| FsChange::Removed(x.id)));
+        return;
+    }
+    if !enter(&node.id, ancestors, errors) {
+        return;
+    }
+    let entries = match read_children(root, &node.id, options, errors) {
+        Ok(entries) => entries,
+        Err(error) => {
+            // keep the previous children
+            errors.push(error);
+            ancestors.pop();
+            return;
+        }
+    };
+    // previous children by path, and their order to report removals
+    let order: Vec<PathBuf> = node.children.iter().map(|x| x.id.clone()).collect();
+    let mut previous: HashMap<PathBuf, Node<PathBuf, FsEntry>> =
+        node.children.drain(..).map(|x| (x.id.clone(), x)).collect();
+    for (path, entry) in entries {
+        match previous.remove(&path) {
+            Some(mut child) => {
+                if child.value != entry {
+                    changes.push(FsChange::Modified(path));
+                    child.value = entry;
+                }
+                rescan_node(
+                    root,
+                    &mut child,
+                    depth + 1,
+                    options,
+                    ancestors,
+                    changes,
+                    errors,
+                );
+                node.children.push(child);
+            }
+            None => {
+                changes.push(FsChange::Added(path.clone()));
+                let child = scan_node(root, &path, entry, depth + 1, options, ancestors, errors);
+                node.children.push(child);
+            }
+        }
+    }
+    changes.extend(
+        order
+            .into_iter()
+            .filter(|x| previous.contains_key(x))
+            .map(FsChange::Removed),
+    );
+    ancestors.pop();
+}
+
+/// Returns whether the children of the entry at `depth` must be scanned
+fn expands(entry: &FsEntry, depth: usize, options: &ScanOptions) -> bool {
+    entry.is_dir() && options.max_depth.is_none_or(|x| depth < x)
+}
+
+/// Push the directory at `path` to the ancestors, unless it is already one of them
+/// because of symbolic links. Returns whether the directory has been entered
+fn enter(path: &Path, ancestors: &mut Vec<PathBuf>, errors: &mut Vec<ScanError>) -> bool {
+    let error = match fs::canonicalize(path) {
+        Ok(canonical) if !ancestors.contains(&canonical) => {
+            ancestors.push(canonical);
+            return true;
+        }
+        Ok(_) => io::Error::other("filesystem loop"),
+        Err(error) => error,
+    };
+    errors.push(ScanError {
+        path: path.to_path_buf(),
+        error,
+    });
+    false
+}
+
+/// Read the entries of the directory at `path` which are not skipped, sorted by name.
+/// Fails only if the directory can't be listed
+fn read_children(
+    root: &Path,
+    path: &Path,
+    options: &ScanOptions,
+    errors: &mut Vec<ScanError>,
+) -> Result<Vec<(PathBuf, FsEntry)>, ScanError> {
+    let dir = fs::read_dir(path).map_err(|error| ScanError {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut children = Vec::new();
+    for item in dir {
+        let item = match item {
+            Ok(item) => item,
+            Err(error) => {
+                errors.push(ScanError {
+                    path: path.to_path_buf(),
+                    error,
+                });
+                continue;
+            }
+        };
+        let path = item.path();
+        let name = item.file_name().to_string_lossy().to_string();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if options.skips(relative, &name) {
+            continue;
+        }
+        match FsEntry::read(&path, name, options.follow_symlinks) {
+            Ok(entry) => children.push((path, entry)),
+            Err(error) => errors.push(ScanError { path, error }),
+        }
+    }
+    children.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Create a directory with `a.txt`, `.hidden`, `src/main.rs`, `src/lib/mod.rs` and `target/out.o`
+    fn fixture() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/lib")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("src/lib/mod.rs"), "").unwrap();
+        fs::write(dir.path().join("target/out.o"), "").unwrap();
+        dir
+    }
+
+    fn relative_paths(scan: &FsScan) -> Vec<String> {
+        let root = scan.tree.root().id();
+        scan.tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| x.id().strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_should_scan_directory() {
+        let dir = fixture();
+        let scan = Tree::scan(dir.path(), &ScanOptions::new()).unwrap();
+        assert!(scan.errors.is_empty());
+        assert_eq!(
+            relative_paths(&scan),
+            vec![
+                "",
+                "a.txt",
+                "src",
+                "src/lib",
+                "src/lib/mod.rs",
+                "src/main.rs",
+                "target",
+                "target/out.o"
+            ]
+        );
+        let file = scan.tree.root().query(&dir.path().join("a.txt")).unwrap();
+        assert_eq!(file.value().name, "a.txt");
+        assert_eq!(file.value().kind, FsKind::File);
+        assert_eq!(file.value().size, 5);
+        assert!(file.value().modified.is_some());
+        assert!(scan.tree.root().value().is_dir());
+    }
+
+    #[test]
+    fn test_should_apply_scan_options() {
+        let dir = fixture();
+        let options = ScanOptions::new()
+            .hidden(true)
+            .exclude("target")
+            .unwrap()
+            .exclude("*.rs")
+            .unwrap();
+        let scan = Tree::scan(dir.path(), &options).unwrap();
+        assert_eq!(
+            relative_paths(&scan),
+            vec!["", ".hidden", "a.txt", "src", "src/lib"]
+        );
+        let scan = Tree::scan(dir.path(), &ScanOptions::new().max_depth(1)).unwrap();
+        assert_eq!(relative_paths(&scan), vec!["", "a.txt", "src", "target"]);
+        assert!(ScanOptions::new().exclude("a{b").is_err());
+        assert!(Tree::scan(dir.path().join("missing"), &ScanOptions::new()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_should_handle_symlinks_and_collect_errors() {
+        let dir = fixture();
+        std::os::unix::fs::symlink(dir.path().join("src"), dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("src/up")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("none"), dir.path().join("broken")).unwrap();
+        // symlinks are not followed by default
+        let scan = Tree::scan(dir.path(), &ScanOptions::new()).unwrap();
+        assert!(scan.errors.is_empty());
+        let link = scan.tree.root().query(&dir.path().join("link")).unwrap();
+        assert_eq!(link.value().kind, FsKind::Symlink);
+        assert_eq!(
+            link.value().target.as_deref(),
+            Some(dir.path().join("src").as_path())
+        );
+        assert!(link.is_leaf());
+        // following symlinks reports broken links and loops, without aborting
+        let scan = Tree::scan(dir.path(), &ScanOptions::new().follow_symlinks(true)).unwrap();
+        let errors: Vec<&Path> = scan.errors.iter().map(|x| x.path.as_path()).collect();
+        assert!(errors.contains(&dir.path().join("broken").as_path()));
+        assert!(errors.contains(&dir.path().join("src/up").as_path()));
+        assert!(scan.tree.root().query(&dir.path().join("broken")).is_none());
+        let link = scan.tree.root().query(&dir.path().join("link")).unwrap();
+        assert_eq!(link.value().kind, FsKind::Directory);
+        assert!(scan
+            .tree
+            .root()
+            .query(&dir.path().join("link/lib/mod.rs"))
+            .is_some());
+    }
+
+    #[test]
+    fn test_should_rescan_directory() {
+        let dir = fixture();
+        let options = ScanOptions::new();
+        let mut tree = Tree::scan(dir.path(), &options).unwrap().tree;
+        fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+        fs::write(dir.path().join("src/new.rs"), "").unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/readme.md"), "").unwrap();
+        fs::remove_dir_all(dir.path().join("target")).unwrap();
+        let rescan = tree.rescan(&options).unwrap();
+        assert!(rescan.errors.is_empty());
+        let changes = rescan.changes;
+        assert!(changes.contains(&FsChange::Modified(dir.path().join("a.txt"))));
+        assert!(changes.contains(&FsChange::Added(dir.path().join("src/new.rs"))));
+        assert!(changes.contains(&FsChange::Added(dir.path().join("docs"))));
+        assert!(changes.contains(&FsChange::Removed(dir.path().join("target"))));
+        // descendants of added and removed directories are not reported
+        assert!(!changes.contains(&FsChange::Added(dir.path().join("docs/readme.md"))));
+        assert!(!changes.contains(&FsChange::Removed(dir.path().join("target/out.o"))));
+        assert!(!changes.contains(&FsChange::Modified(dir.path().join("src/main.rs"))));
+        // the tree is the same as a new scan
+        assert_eq!(tree, Tree::scan(dir.path(), &options).unwrap().tree);
+        assert!(tree.rescan(&options).unwrap().changes.is_empty());
+        fs::remove_dir_all(dir.path()).unwrap();
+        assert!(tree.rescan(&options).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+//! # Isomorphism
+//!
+//! Compare trees regardless of the order of siblings
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::{Node, Tree};
+
+/// Canonical labels of subtrees (AHU), shared by the trees being compared
+struct Canonical<C> {
+    /// Returns the class of equal values of a value
+    class: C,
+    labels: HashMap<(usize, Vec<usize>), usize>,
+}
+
+impl<C> Canonical<C> {
+    /// Returns the canonical label of the subtree of `node`
+    fn label<'a, U, T>(&mut self, node: &'a Node<U, T>) -> usize
+    where
+        C: FnMut(&'a T) -> usize,
+    {
+        let mut children: Vec<usize> = node.children.iter().map(|x| self.label(x)).collect();
+        children.sort_unstable();
+        let key = ((self.class)(&node.value), children);
+        let next = self.labels.len();
+        *self.labels.entry(key).or_insert(next)
+    }
+}
+
+/// Returns whether `a` and `b` are isomorphic, where `class` returns the class of equal values of each value
+fn isomorphic<'a, U, V, T, C>(a: &'a Node<U, T>, b: &'a Node<V, T>, class: C) -> bool
+where
+    U: PartialEq,
+    V: PartialEq,
+    C: FnMut(&'a T) -> usize,
+{
+    if a.count() != b.count() {
+        return false;
+    }
+    let mut canonical = Canonical {
+        class,
+        labels: HashMap::new(),
+    };
+    canonical.label(a) == canonical.label(b)
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Returns whether this [`Node`] is equal to `other`, matching children by id regardless of their order.
+    /// Nodes whose children share an id, on either side, are never equal
+    ///
+    /// ```rust
+    /// use orange_trees::Node;
+    ///
+    /// let a: Node<&str, usize> = Node::new("/", 0).with_child(Node::new("/a", 1)).with_child(Node::new("/b", 2));
+    /// let b: Node<&str, usize> = Node::new("/", 0).with_child(Node::new("/b", 2)).with_child(Node::new("/a", 1));
+    /// assert_ne!(a, b);
+    /// assert!(a.eq_unordered(&b));
+    /// ```
+    pub fn eq_unordered(&self, other: &Self) -> bool
+    where
+        U: Eq + Hash,
+        T: PartialEq,
+    {
+        if self.id != other.id
+            || self.value != other.value
+            || self.children.len() != other.children.len()
+        {
+            return false;
+        }
+        // children are matched by id, so both sides must have unique ids among siblings
+        let ids: HashSet<&U> = self.children.iter().map(|x| &x.id).collect();
+        let children: HashMap<&U, &Self> = other.children.iter().map(|x| (&x.id, x)).collect();
+        ids.len() == self.children.len()
+            && children.len() == other.children.len()
+            && self.children.iter().all(|x| {
+                children
+                    .get(&x.id)
+                    .is_some_and(|other| x.eq_unordered(other))
+            })
+    }
+
+    /// Returns whether this [`Node`] and `other` have the same shape and equal values, regardless of ids and of
+    /// the order of siblings.
+    ///
+    /// Subtrees are compared through their canonical labels (AHU algorithm), hashing the values,
+    /// so the check takes `O(n log n)` time in the number of nodes
+    ///
+    /// ```rust
+    /// use orange_trees::Node;
+    ///
+    /// let a: Node<usize, &str> = Node::new(0, "a").with_child(Node::new(1, "b")).with_child(Node::new(2, "c"));
+    /// let b: Node<&str, &str> = Node::new("x", "a").with_child(Node::new("z", "c")).with_child(Node::new("y", "b"));
+    /// let c: Node<&str, &str> = Node::new("x", "A").with_child(Node::new("z", "C")).with_child(Node::new("y", "B"));
+    /// assert!(a.is_isomorphic(&b));
+    /// assert!(!a.is_isomorphic(&c));
+    /// assert!(a.is_isomorphic_by_key(&c, |x| x.to_ascii_lowercase()));
+    /// ```
+    pub fn is_isomorphic<V>(&self, other: &Node<V, T>) -> bool
+    where
+        V: PartialEq,
+        T: Hash + Eq,
+    {
+        let mut classes: HashMap<&T, usize> = HashMap::new();
+        isomorphic(self, other, |x| {
+            let next = classes.len();
+            *classes.entry(x).or_insert(next)
+        })
+    }
+
+    /// Returns whether this [`Node`] and `other` are isomorphic, comparing the keys returned by `key`
+    /// instead of the values. See [`Node::is_isomorphic`]
+    pub fn is_isomorphic_by_key<V, K, F>(&self, other: &Node<V, T>, key: F) -> bool
+    where
+        V: PartialEq,
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+    {
+        let mut classes: HashMap<K, usize> = HashMap::new();
+        isomorphic(self, other, |x| {
+            let next = classes.len();
+            *classes.entry(key(x)).or_insert(next)
+        })
+    }
+
+    /// Returns whether this [`Node`] and `other` are isomorphic, comparing values with `value_eq`,
+    /// which must be an equivalence relation. See [`Node::is_isomorphic`].
+    ///
+    /// Values can't be hashed, so each value is compared with a value of each class of equal values met so far:
+    /// the check is quadratic when values are all distinct. Prefer [`Node::is_isomorphic_by_key`] when possible
+    pub fn is_isomorphic_by<V, E>(&self, other: &Node<V, T>, value_eq: E) -> bool
+    where
+        V: PartialEq,
+        E: Fn(&T, &T) -> bool,
+    {
+        let mut classes: Vec<&T> = Vec::new();
+        isomorphic(self, other, |x| {
+            match classes.iter().position(|y| value_eq(y, x)) {
+                Some(class) => class,
+                None => {
+                    classes.push(x);
+                    classes.len() - 1
+                }
+            }
+        })
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Returns whether this [`Tree`] is equal to `other`, regardless of the order of siblings.
+    /// See [`Node::eq_unordered`]
+    pub fn eq_unordered(&self, other: &Self) -> bool
+    where
+        U: Eq + Hash,
+        T: PartialEq,
+    {
+        self.root.eq_unordered(other.root())
+    }
+
+    /// Returns whether this [`Tree`] and `other` are isomorphic, ignoring ids. See [`Node::is_isomorphic`]
+    pub fn is_isomorphic<V>(&self, other: &Tree<V, T>) -> bool
+    where
+        V: PartialEq,
+        T: Hash + Eq,
+    {
+        self.root.is_isomorphic(other.root())
+    }
+
+    /// Returns whether this [`Tree`] and `other` are isomorphic, comparing the keys returned by `key`.
+    /// See [`Node::is_isomorphic_by_key`]
+    pub fn is_isomorphic_by_key<V, K, F>(&self, other: &Tree<V, T>, key: F) -> bool
+    where
+        V: PartialEq,
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+    {
+        self.root.is_isomorphic_by_key(other.root(), key)
+    }
+
+    /// Returns whether this [`Tree`] and `other` are isomorphic, comparing values with `value_eq`.
+    /// See [`Node::is_isomorphic_by`]
+    pub fn is_isomorphic_by<V, E>(&self, other: &Tree<V, T>, value_eq: E) -> bool
+    where
+        V: PartialEq,
+        E: Fn(&T, &T) -> bool,
+    {
+        self.root.is_isomorphic_by(other.root(), value_eq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    fn shuffled() -> Tree<&'static str, usize> {
+        Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/a", 5).with_child(Node::new("/tmp/a/b", 6))),
+                )
+                .with_child(
+                    Node::new("/bin", 1)
+                        .with_child(Node::new("/bin/pwd", 3))
+                        .with_child(Node::new("/bin/ls", 2)),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_should_compare_unordered() {
+        assert!(tree().eq_unordered(&tree()));
+        assert!(tree().eq_unordered(&shuffled()));
+        assert!(shuffled().eq_unordered(&tree()));
+        let mut other = shuffled();
+        other.root_mut().query_mut(&"/bin/ls").unwrap().set_value(7);
+        assert!(!tree().eq_unordered(&other));
+        let mut other = shuffled();
+        other
+            .root_mut()
+            .query_mut(&"/tmp/a")
+            .unwrap()
+            .remove_child(&"/tmp/a/b");
+        assert!(!tree().eq_unordered(&other));
+        assert!(!other.eq_unordered(&tree()));
+        // same children count, different ids
+        let mut other = shuffled();
+        other
+            .root_mut()
+            .query_mut(&"/bin")
+            .unwrap()
+            .remove_child(&"/bin/ls");
+        other
+            .root_mut()
+            .query_mut(&"/bin")
+            .unwrap()
+            .add_child(Node::new("/bin/sh", 2));
+        assert!(!tree().eq_unordered(&other));
+        // siblings with the same id, as made by map_ids, never compare equal, whichever the side
+        let distinct = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("a", 1))
+                .with_child(Node::new("b", 1)),
+        );
+        let duplicate = distinct.clone().map_ids(|x| if x == "b" { "a" } else { x });
+        assert!(!duplicate.eq_unordered(&distinct));
+        assert!(!distinct.eq_unordered(&duplicate));
+    }
+
+    #[test]
+    fn test_should_check_isomorphism() {
+        assert!(tree().is_isomorphic(&shuffled()));
+        // ids are ignored
+        let renamed: Tree<usize, usize> = Tree::new(
+            Node::new(0, 0)
+                .with_child(
+                    Node::new(1, 1)
+                        .with_child(Node::new(2, 3))
+                        .with_child(Node::new(3, 2)),
+                )
+                .with_child(
+                    Node::new(4, 4).with_child(Node::new(5, 5).with_child(Node::new(6, 6))),
+                ),
+        );
+        assert!(tree().is_isomorphic(&renamed));
+        // values are compared by key or with value_eq
+        let mut other = shuffled();
+        other
+            .root_mut()
+            .query_mut(&"/bin/ls")
+            .unwrap()
+            .set_value(12);
+        assert!(!tree().is_isomorphic(&other));
+        assert!(tree().is_isomorphic_by_key(&other, |x| x % 10));
+        assert!(tree().is_isomorphic_by(&other, |x, y| x % 10 == y % 10));
+        assert!(!tree().is_isomorphic_by(&other, |x, y| x == y));
+        // different shape with the same values
+        let moved: Tree<usize, usize> = Tree::new(
+            Node::new(0, 0)
+                .with_child(Node::new(1, 1).with_child(Node::new(2, 2)))
+                .with_child(
+                    Node::new(4, 4)
+                        .with_child(Node::new(3, 3))
+                        .with_child(Node::new(5, 5).with_child(Node::new(6, 6))),
+                ),
+        );
+        assert!(!tree().is_isomorphic(&moved));
+        assert_eq!(tree().root().count(), moved.root().count());
+        // all values equal: only the shape matters
+        let shape: Tree<usize, usize> = Tree::new(
+            Node::new(0, 9)
+                .with_child(Node::new(1, 9).with_child(Node::new(2, 9).with_child(Node::new(3, 9))))
+                .with_child(
+                    Node::new(4, 9)
+                        .with_child(Node::new(5, 9))
+                        .with_child(Node::new(6, 9)),
+                ),
+        );
+        assert!(!tree().is_isomorphic(&shape));
+        assert!(tree().is_isomorphic_by_key(&shape, |_| ()));
+        assert!(tree().is_isomorphic_by(&shape, |_, _| true));
+    }
+}
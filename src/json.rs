@@ -0,0 +1,184 @@
+//! # JSON
+//!
+//! Convert JSON value documents (and any serde data format through them) into trees and back
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::{Node, Tree};
+
+/// The value of a node converted from a JSON value.
+/// Objects and arrays are represented by their children.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Scalar {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    /// An array, whose items are the children of the node
+    Array,
+    /// An object, whose members are the children of the node
+    Object,
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(x) => write!(f, "{x}"),
+            Self::Number(x) => write!(f, "{x}"),
+            Self::String(x) => write!(f, "{x}"),
+            Self::Array => write!(f, "[]"),
+            Self::Object => write!(f, "{{}}"),
+        }
+    }
+}
+
+impl Tree<String, Scalar> {
+    /// Convert a JSON value into a [`Tree`].
+    ///
+    /// Each node has its JSON pointer as id (e.g. `/servers/0/name`; the root id is empty).
+    ///
+    /// ```rust
+    /// use orange_trees::{Scalar, Tree};
+    /// use serde_json::json;
+    ///
+    /// let tree = Tree::from_json(&json!({ "servers": [{ "name": "a" }, { "name": "b" }] }));
+    /// assert_eq!(
+    ///     tree.root().query(&"/servers/1/name".to_string()).unwrap().value(),
+    ///     &Scalar::String("b".to_string())
+    /// );
+    /// ```
+    pub fn from_json(value: &Value) -> Self {
+        /// Private recursive call for from_json
+        fn from_json_r(pointer: String, value: &Value) -> Node<String, Scalar> {
+            match value {
+                Value::Null => Node::new(pointer, Scalar::Null),
+                Value::Bool(x) => Node::new(pointer, Scalar::Bool(*x)),
+                Value::Number(x) => Node::new(pointer, Scalar::Number(x.clone())),
+                Value::String(x) => Node::new(pointer, Scalar::String(x.clone())),
+                Value::Array(items) => {
+                    let children = items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, x)| from_json_r(format!("{pointer}/{i}"), x))
+                        .collect();
+                    Node::new(pointer, Scalar::Array).with_children(children)
+                }
+                Value::Object(members) => {
+                    let children = members
+                        .iter()
+                        .map(|(k, x)| from_json_r(format!("{pointer}/{}", escape(k)), x))
+                        .collect();
+                    Node::new(pointer, Scalar::Object).with_children(children)
+                }
+            }
+        }
+        Tree::new(from_json_r(String::new(), value))
+    }
+
+    /// Convert any serializable value into a [`Tree`], passing through its JSON representation.
+    ///
+    /// This allows to convert documents of other serde data formats, such as YAML or TOML values.
+    pub fn from_serializable<S: Serialize>(value: &S) -> Result<Self, serde_json::Error> {
+        serde_json::to_value(value).map(|x| Self::from_json(&x))
+    }
+
+    /// Convert the [`Tree`] back into a JSON value.
+    ///
+    /// Member names are taken from the last segment of the JSON pointer of each child of an object;
+    /// children of scalar nodes are ignored.
+    pub fn to_json(&self) -> Value {
+        /// Private recursive call for to_json
+        fn to_json_r(node: &Node<String, Scalar>) -> Value {
+            match &node.value {
+                Scalar::Null => Value::Null,
+                Scalar::Bool(x) => Value::Bool(*x),
+                Scalar::Number(x) => Value::Number(x.clone()),
+                Scalar::String(x) => Value::String(x.clone()),
+                Scalar::Array => Value::Array(node.children.iter().map(to_json_r).collect()),
+                Scalar::Object => Value::Object(
+                    node.children
+                        .iter()
+                        .map(|x| (member_name(&x.id), to_json_r(x)))
+                        .collect::<Map<String, Value>>(),
+                ),
+            }
+        }
+        to_json_r(self.root())
+    }
+
+    /// Convert the [`Tree`] into any deserializable value, passing through its JSON representation.
+    pub fn deserialize_as<D: DeserializeOwned>(&self) -> Result<D, serde_json::Error> {
+        serde_json::from_value(self.to_json())
+    }
+}
+
+/// Escape a member name as a JSON pointer reference token
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// Get the member name from the last reference token of a JSON pointer
+fn member_name(pointer: &str) -> String {
+    let token = pointer.rsplit('/').next().unwrap_or_default();
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::BTreeMap;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_should_convert_json_into_tree() {
+        let value = json!({
+            "name": "orange",
+            "a/b~c": null,
+            "tags": ["tree", 1, true],
+        });
+        let tree = Tree::from_json(&value);
+        let nodes: Vec<(&str, String)> = tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| (x.id().as_str(), x.value().to_string()))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                ("", "{}".to_string()),
+                ("/a~1b~0c", "null".to_string()),
+                ("/name", "orange".to_string()),
+                ("/tags", "[]".to_string()),
+                ("/tags/0", "tree".to_string()),
+                ("/tags/1", "1".to_string()),
+                ("/tags/2", "true".to_string()),
+            ]
+        );
+        assert_eq!(tree.to_json(), value);
+    }
+
+    #[test]
+    fn test_should_convert_serializable_values() {
+        let mut value: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        value.insert("primes".to_string(), vec![2, 3, 5]);
+        let mut tree = Tree::from_serializable(&value).unwrap();
+        assert_eq!(tree.root().count(), 5);
+        tree.root_mut()
+            .query_mut(&"/primes".to_string())
+            .unwrap()
+            .add_child(Node::new("/primes/3".to_string(), Scalar::Number(7.into())));
+        let value: BTreeMap<String, Vec<u32>> = tree.deserialize_as().unwrap();
+        assert_eq!(value["primes"], vec![2, 3, 5, 7]);
+        assert!(tree.deserialize_as::<Vec<u32>>().is_err());
+    }
+}
@@ -10,6 +10,13 @@
 //! orange-trees = "0.1"
 //! ```
 //!
+//! ### Features
+//!
+//! - `fs`: scan directories of the filesystem into trees
+//! - `serde`: serialize and deserialize patches
+//! - `json`: convert JSON values, and any serde data format through them, into trees and back
+//! - `xml`: convert XML documents into trees and back
+//!
 //! ### Initialize a tree
 //!
 //! Orange-trees provides three ways to initialize trees:
@@ -110,6 +117,8 @@
 //! - Adding and removing children
 //! - Sorting node children
 //! - Truncating a node by depth
+//! - Inserting nodes by path, creating the missing ancestors
+//! - Filtering nodes by predicate
 //!
 //! ```rust
 //! use orange_trees::{Node, Tree};
@@ -213,6 +222,58 @@
 use std::cmp::Ordering;
 use std::slice::{Iter, IterMut};
 
+// modules
+mod aggregate;
+mod binary;
+mod cascade;
+mod diff;
+mod edit_distance;
+mod filter;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "fs")]
+mod fs;
+mod isomorphism;
+#[cfg(feature = "json")]
+mod json;
+mod map;
+mod markdown;
+mod merge;
+mod merkle;
+mod newick;
+mod outline;
+mod patch;
+mod paths;
+mod pattern;
+mod relational;
+mod set;
+mod sexpr;
+mod shared;
+#[cfg(feature = "xml")]
+mod xml;
+
+pub use binary::{BinaryCodec, BinaryError, BytesCodec, DecodeError, NumberCodec, StringCodec};
+pub use diff::{diff, DiffError, Edit};
+pub use edit_distance::EditDistance;
+pub use filter::FilterMode;
+#[cfg(feature = "fs")]
+pub use fs::{FsChange, FsEntry, FsKind, FsRescan, FsScan, ScanError, ScanOptions};
+#[cfg(feature = "json")]
+pub use json::Scalar;
+pub use map::MapContext;
+pub use merge::{merge3, Conflict, Merged, Resolution, Side};
+pub use merkle::{changed_subtrees, MerkleHash, SubtreeChange};
+pub use newick::{NewickError, NewickValue};
+pub use outline::OutlineError;
+pub use patch::{Patch, PatchError};
+pub use paths::{PathStyle, PathsError};
+pub use pattern::{Pattern, PatternKind};
+pub use relational::{ClosureRow, NestedSetRow, RelationalError};
+pub use sexpr::SexprError;
+pub use shared::{SharedNode, SharedShape, SharedTree};
+#[cfg(feature = "xml")]
+pub use xml::{XmlElement, XmlError, XmlIdStrategy};
+
 /// represent the tree data structure inside the component.
 /// U: is the type for the [`Node`] indentifier (must implement [`PartialEq`])
 /// T: is the type for the [`Node`] value
@@ -236,6 +297,45 @@ impl<U: PartialEq, T> Tree<U, T> {
     pub fn root_mut(&mut self) -> &mut Node<U, T> {
         &mut self.root
     }
+
+    /// Walk the path described by `segments`, starting from the children of the root, creating the missing nodes.
+    /// See [`Node::insert_path`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let mut tree: Tree<&str, &str> = Tree::new(Node::new("/", "/"));
+    /// tree.insert_path(["/home", "/home/omar", "/home/omar/.bashrc"], ".bashrc", |_| "dir");
+    /// tree.insert_path(["/home", "/home/omar"], "omar/", |_| "dir");
+    /// assert_eq!(tree.get_path(&["/home"]).unwrap().value(), &"dir");
+    /// assert_eq!(tree.get_path(&["/home", "/home/omar"]).unwrap().value(), &"omar/");
+    /// assert_eq!(tree.root().query(&"/home/omar/.bashrc").unwrap().value(), &".bashrc");
+    /// ```
+    pub fn insert_path<I, F>(
+        &mut self,
+        segments: I,
+        leaf_value: T,
+        intermediate_factory: F,
+    ) -> &mut Node<U, T>
+    where
+        I: IntoIterator<Item = U>,
+        F: FnMut(&U) -> T,
+    {
+        self.root
+            .insert_path(segments, leaf_value, intermediate_factory)
+    }
+
+    /// Returns the node at the end of the path described by `segments`, starting from the children of the root.
+    /// See [`Node::get_path`]
+    pub fn get_path(&self, segments: &[U]) -> Option<&Node<U, T>> {
+        self.root.get_path(segments)
+    }
+
+    /// Returns the mutable node at the end of the path described by `segments`, starting from the children of the root.
+    /// See [`Node::get_path_mut`]
+    pub fn get_path_mut(&mut self, segments: &[U]) -> Option<&mut Node<U, T>> {
+        self.root.get_path_mut(segments)
+    }
 }
 
 /// Describes a node inside the [`Tree`]
@@ -477,6 +577,58 @@ impl<U: PartialEq, T> Node<U, T> {
         }
     }
 
+    /// Given the ids of the nodes to walk through, starting from the children of this node,
+    /// returns the node at the end of the path
+    pub fn get_path(&self, segments: &[U]) -> Option<&Self> {
+        segments.iter().try_fold(self, |node, segment| {
+            node.children.iter().find(|x| x.id() == segment)
+        })
+    }
+
+    /// Given the ids of the nodes to walk through, starting from the children of this node,
+    /// returns the mutable [`Node`] at the end of the path
+    pub fn get_path_mut(&mut self, segments: &[U]) -> Option<&mut Self> {
+        segments.iter().try_fold(self, |node, segment| {
+            node.children.iter_mut().find(|x| x.id() == segment)
+        })
+    }
+
+    /// Walk the path described by `segments`, starting from the children of this node, and set `leaf_value`
+    /// to the node at the end of the path.
+    /// Missing nodes are created as last child of their parent; intermediate nodes get the value returned by
+    /// `intermediate_factory` for their id, while existing nodes keep their value.
+    /// Returns the node at the end of the path, which is this node if `segments` is empty
+    pub fn insert_path<I, F>(
+        &mut self,
+        segments: I,
+        leaf_value: T,
+        mut intermediate_factory: F,
+    ) -> &mut Self
+    where
+        I: IntoIterator<Item = U>,
+        F: FnMut(&U) -> T,
+    {
+        let mut node = self;
+        let mut segments = segments.into_iter().peekable();
+        while let Some(segment) = segments.next() {
+            let index = match node.children.iter().position(|x| x.id == segment) {
+                Some(index) => index,
+                None if segments.peek().is_none() => {
+                    node.children.push(Node::new(segment, leaf_value));
+                    return node.children.last_mut().unwrap();
+                }
+                None => {
+                    let value = intermediate_factory(&segment);
+                    node.children.push(Node::new(segment, value));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+        }
+        node.set_value(leaf_value);
+        node
+    }
+
     /// Calculate the route of a [`Node`] by its id
     pub fn route_by_node(&self, id: &U) -> Option<Vec<usize>> {
         // Recursive function
@@ -513,6 +665,18 @@ impl<U: PartialEq, T> Node<U, T> {
     }
 }
 
+// -- utils
+
+/// Format a route as a dotted path of one-based positions (e.g. `[0, 2, 1]` is `1.3.2`).
+/// Routes are relative to the root, so `1` is the first child of the root, whose path is empty
+pub(crate) fn dotted_route(route: &[usize]) -> String {
+    route
+        .iter()
+        .map(|x| (x + 1).to_string())
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
 // -- node macro
 
 #[macro_export]
@@ -937,4 +1101,35 @@ mod tests {
         assert_eq!(node.children().len(), 1);
         assert_eq!(node.children()[0].value(), &2);
     }
+
+    #[test]
+    fn test_should_insert_and_get_paths() {
+        let mut tree: Tree<&'static str, usize> = Tree::new(Node::new("/", 0));
+        let mut created = Vec::new();
+        let leaf = tree.insert_path(["home", "omar", "docs"], 3, |x| {
+            created.push(*x);
+            1
+        });
+        assert_eq!(leaf.id(), &"docs");
+        assert_eq!(created, vec!["home", "omar"]);
+        // existing nodes are walked, not duplicated
+        tree.insert_path(["home", "omar", "music"], 4, |_| 9);
+        tree.insert_path(["home", "omar"], 2, |_| 9);
+        assert_eq!(tree.root().count(), 5);
+        assert_eq!(tree.get_path(&["home"]).unwrap().value(), &1);
+        assert_eq!(tree.get_path(&["home", "omar"]).unwrap().value(), &2);
+        assert_eq!(
+            tree.get_path(&["home", "omar", "music"]).unwrap().value(),
+            &4
+        );
+        assert!(tree.get_path(&["omar"]).is_none());
+        assert_eq!(tree.get_path(&[]).unwrap().id(), &"/");
+        tree.get_path_mut(&["home", "omar", "docs"])
+            .unwrap()
+            .set_value(5);
+        assert_eq!(tree.root().node_by_route(&[0, 0, 0]).unwrap().value(), &5);
+        // empty path sets the value of the root
+        tree.insert_path([], 7, |_| 9);
+        assert_eq!(tree.root().value(), &7);
+    }
 }
@@ -0,0 +1,300 @@
+//! # Map
+//!
+//! Map ids and values of trees into new trees with the same shape
+
+use crate::{Node, Tree};
+
+/// The context of a node passed to [`Node::map_with_context`]
+#[derive(Debug)]
+pub struct MapContext<'a, T> {
+    /// Depth of the node, where the mapped node is `0`
+    pub depth: usize,
+    /// Values of the ancestors of the node, from the mapped node to the parent
+    pub ancestors: &'a [&'a T],
+}
+
+impl<T> MapContext<'_, T> {
+    /// Returns the value of the parent of the node, if any
+    pub fn parent(&self) -> Option<&T> {
+        self.ancestors.last().copied()
+    }
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Consume the [`Node`] and map its values, and the ones of its descendants, with `f`.
+    /// Nodes are visited in pre-order
+    pub fn map_values<V, F>(self, mut f: F) -> Node<U, V>
+    where
+        F: FnMut(T) -> V,
+    {
+        /// Private recursive call for map_values
+        fn map_values_r<U, T, V, F: FnMut(T) -> V>(node: Node<U, T>, f: &mut F) -> Node<U, V> {
+            let value = f(node.value);
+            Node {
+                id: node.id,
+                value,
+                children: node
+                    .children
+                    .into_iter()
+                    .map(|x| map_values_r(x, f))
+                    .collect(),
+            }
+        }
+        map_values_r(self, &mut f)
+    }
+
+    /// Map the values of the [`Node`] and of its descendants with `f` into a new node, cloning the ids.
+    /// Nodes are visited in pre-order
+    pub fn map_values_ref<V, F>(&self, mut f: F) -> Node<U, V>
+    where
+        U: Clone,
+        F: FnMut(&T) -> V,
+    {
+        self.map_with_context(|value, _| f(value))
+    }
+
+    /// Consume the [`Node`] and map its ids, and the ones of its descendants, with `f`.
+    /// Nodes are visited in pre-order
+    pub fn map_ids<U2, F>(self, mut f: F) -> Node<U2, T>
+    where
+        F: FnMut(U) -> U2,
+    {
+        /// Private recursive call for map_ids
+        fn map_ids_r<U, U2, T, F: FnMut(U) -> U2>(node: Node<U, T>, f: &mut F) -> Node<U2, T> {
+            Node {
+                id: f(node.id),
+                value: node.value,
+                children: node.children.into_iter().map(|x| map_ids_r(x, f)).collect(),
+            }
+        }
+        map_ids_r(self, &mut f)
+    }
+
+    /// Map the ids of the [`Node`] and of its descendants with `f` into a new node, cloning the values.
+    /// Nodes are visited in pre-order
+    pub fn map_ids_ref<U2, F>(&self, mut f: F) -> Node<U2, T>
+    where
+        T: Clone,
+        F: FnMut(&U) -> U2,
+    {
+        /// Private recursive call for map_ids_ref
+        fn map_ids_ref_r<U, U2, T: Clone, F: FnMut(&U) -> U2>(
+            node: &Node<U, T>,
+            f: &mut F,
+        ) -> Node<U2, T> {
+            Node {
+                id: f(&node.id),
+                value: node.value.clone(),
+                children: node.children.iter().map(|x| map_ids_ref_r(x, f)).collect(),
+            }
+        }
+        map_ids_ref_r(self, &mut f)
+    }
+
+    /// Consume the [`Node`] and map both ids and values of it and its descendants with `f`,
+    /// stopping at the first error. Nodes are visited in pre-order
+    pub fn try_map<U2, V, E, F>(self, mut f: F) -> Result<Node<U2, V>, E>
+    where
+        F: FnMut(U, T) -> Result<(U2, V), E>,
+    {
+        /// Private recursive call for try_map
+        fn try_map_r<U, T, U2, V, E, F: FnMut(U, T) -> Result<(U2, V), E>>(
+            node: Node<U, T>,
+            f: &mut F,
+        ) -> Result<Node<U2, V>, E> {
+            let (id, value) = f(node.id, node.value)?;
+            Ok(Node {
+                id,
+                value,
+                children: node
+                    .children
+                    .into_iter()
+                    .map(|x| try_map_r(x, f))
+                    .collect::<Result<_, E>>()?,
+            })
+        }
+        try_map_r(self, &mut f)
+    }
+
+    /// Map the values of the [`Node`] and of its descendants with `f` into a new node, cloning the ids.
+    /// `f` receives the value of the node and its [`MapContext`], with the depth and the values of the ancestors.
+    /// Nodes are visited in pre-order
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let tree: Tree<&str, &str> = Tree::new(
+    ///     Node::new("/", "").with_child(Node::new("/home", "home").with_child(Node::new("/home/omar", "omar"))),
+    /// );
+    /// let paths = tree.map_with_context(|value, ctx| {
+    ///     let mut path: Vec<&str> = ctx.ancestors.iter().map(|x| **x).collect();
+    ///     path.push(*value);
+    ///     format!("{}@{}", path.join("/"), ctx.depth)
+    /// });
+    /// assert_eq!(paths.root().query(&"/home/omar").unwrap().value(), "/home/omar@2");
+    /// ```
+    pub fn map_with_context<V, F>(&self, mut f: F) -> Node<U, V>
+    where
+        U: Clone,
+        F: FnMut(&T, &MapContext<'_, T>) -> V,
+    {
+        /// Private recursive call for map_with_context
+        fn map_with_context_r<'a, U: Clone, T, V, F: FnMut(&T, &MapContext<'_, T>) -> V>(
+            node: &'a Node<U, T>,
+            ancestors: &mut Vec<&'a T>,
+            f: &mut F,
+        ) -> Node<U, V> {
+            let ctx = MapContext {
+                depth: ancestors.len(),
+                ancestors,
+            };
+            let value = f(&node.value, &ctx);
+            ancestors.push(&node.value);
+            let children = node
+                .children
+                .iter()
+                .map(|x| map_with_context_r(x, ancestors, f))
+                .collect();
+            ancestors.pop();
+            Node {
+                id: node.id.clone(),
+                value,
+                children,
+            }
+        }
+        map_with_context_r(self, &mut Vec::new(), &mut f)
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Consume the [`Tree`] and map its values with `f`. See [`Node::map_values`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let tree: Tree<&str, usize> = Tree::new(Node::new("a", 1).with_child(Node::new("b", 2)));
+    /// let tree: Tree<&str, String> = tree.map_values(|x| x.to_string());
+    /// assert_eq!(tree.root().query(&"b").unwrap().value(), "2");
+    /// ```
+    pub fn map_values<V, F>(self, f: F) -> Tree<U, V>
+    where
+        F: FnMut(T) -> V,
+    {
+        Tree::new(self.root.map_values(f))
+    }
+
+    /// Map the values of the [`Tree`] with `f` into a new tree. See [`Node::map_values_ref`]
+    pub fn map_values_ref<V, F>(&self, f: F) -> Tree<U, V>
+    where
+        U: Clone,
+        F: FnMut(&T) -> V,
+    {
+        Tree::new(self.root.map_values_ref(f))
+    }
+
+    /// Consume the [`Tree`] and map its ids with `f`. See [`Node::map_ids`]
+    pub fn map_ids<U2: PartialEq, F>(self, f: F) -> Tree<U2, T>
+    where
+        F: FnMut(U) -> U2,
+    {
+        Tree::new(self.root.map_ids(f))
+    }
+
+    /// Map the ids of the [`Tree`] with `f` into a new tree. See [`Node::map_ids_ref`]
+    pub fn map_ids_ref<U2: PartialEq, F>(&self, f: F) -> Tree<U2, T>
+    where
+        T: Clone,
+        F: FnMut(&U) -> U2,
+    {
+        Tree::new(self.root.map_ids_ref(f))
+    }
+
+    /// Consume the [`Tree`] and map its ids and values with `f`, stopping at the first error.
+    /// See [`Node::try_map`]
+    pub fn try_map<U2: PartialEq, V, E, F>(self, f: F) -> Result<Tree<U2, V>, E>
+    where
+        F: FnMut(U, T) -> Result<(U2, V), E>,
+    {
+        self.root.try_map(f).map(Tree::new)
+    }
+
+    /// Map the values of the [`Tree`] with `f`, which receives the [`MapContext`] of each node, into a new tree.
+    /// See [`Node::map_with_context`]
+    pub fn map_with_context<V, F>(&self, f: F) -> Tree<U, V>
+    where
+        U: Clone,
+        F: FnMut(&T, &MapContext<'_, T>) -> V,
+    {
+        Tree::new(self.root.map_with_context(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_map_values_and_ids() {
+        let tree = tree();
+        let mut visited = Vec::new();
+        let doubled = tree.map_values_ref(|x| {
+            visited.push(*x);
+            x * 2
+        });
+        assert_eq!(visited, vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(doubled.root().query(&"/bin/pwd").unwrap().value(), &6);
+        let ids = tree.map_ids_ref(|x| x.len());
+        assert_eq!(ids.root().node_by_route(&[0, 1]).unwrap().id(), &8);
+        let tree = tree.map_ids(|x| x.to_uppercase()).map_values(|x| x + 1);
+        assert_eq!(
+            tree.root().query(&"/BIN/LS".to_string()).unwrap().value(),
+            &3
+        );
+        assert_eq!(tree.root().count(), 7);
+    }
+
+    #[test]
+    fn test_should_try_map() {
+        let mapped: Result<Tree<String, u8>, String> =
+            tree().try_map(|id, value| Ok((id.trim_start_matches('/').to_string(), value as u8)));
+        let mapped = mapped.unwrap();
+        assert_eq!(
+            mapped.root().query(&"bin/ls".to_string()).unwrap().value(),
+            &2
+        );
+        let mut visited = 0;
+        let failed: Result<Tree<&str, usize>, String> = tree().try_map(|id, value| {
+            visited += 1;
+            match value {
+                2 => Err(format!("bad {id}")),
+                value => Ok((id, value)),
+            }
+        });
+        assert_eq!(failed.unwrap_err(), "bad /bin/ls");
+        assert_eq!(visited, 3);
+    }
+
+    #[test]
+    fn test_should_map_with_context() {
+        let tree = tree().map_with_context(|value, ctx| {
+            (
+                ctx.depth,
+                ctx.parent().copied(),
+                ctx.ancestors.iter().copied().sum::<usize>() + value,
+            )
+        });
+        assert_eq!(tree.root().value(), &(0, None, 0));
+        assert_eq!(
+            tree.root().query(&"/bin/pwd").unwrap().value(),
+            &(2, Some(1), 4)
+        );
+        assert_eq!(
+            tree.root().query(&"/tmp").unwrap().value(),
+            &(1, Some(0), 4)
+        );
+    }
+}
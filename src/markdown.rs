@@ -0,0 +1,251 @@
+//! # Markdown
+//!
+//! Convert Markdown heading hierarchies and nested lists into trees and back
+
+use std::fmt;
+
+use crate::{dotted_route, Node, Tree};
+
+/// An open ancestor while reading the document
+enum Frame {
+    /// A heading and its level
+    Heading(usize),
+    /// A list item and its indentation width
+    Item(usize),
+}
+
+impl Tree<String, String> {
+    /// Build a [`Tree`] from the outline of a Markdown document.
+    ///
+    /// Headings (`#`, `##`, ...) become children of the closest heading with a lower level,
+    /// while list items (`-`, `*`, `+`, `1.`) become children of the less indented item or of the heading they follow.
+    /// Paragraphs and fenced code blocks are ignored.
+    ///
+    /// The root represents the document and has an empty id and value;
+    /// every other node has the text as value and its position as id (e.g. `1.3.2`, where `1` is the first child of the root).
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let tree = Tree::from_markdown("# Intro\n\n## Goals\n\n- fast\n  - really fast\n- small\n\n# Usage\n");
+    /// assert_eq!(tree.root().count(), 7);
+    /// assert_eq!(tree.root().query(&"1.1.1.1".to_string()).unwrap().value(), "really fast");
+    /// ```
+    pub fn from_markdown(text: &str) -> Self {
+        let mut tree = Tree::new(Node::new(String::new(), String::new()));
+        // open ancestors with their route
+        let mut stack: Vec<(Frame, Vec<usize>)> = Vec::new();
+        let mut fence: Option<&str> = None;
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            // skip code blocks
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|x| trimmed.starts_with(x)) {
+                fence = Some(marker);
+                continue;
+            }
+            let (frame, text) = if let Some((level, text)) = parse_heading(line) {
+                while stack
+                    .last()
+                    .map(|(x, _)| match x {
+                        Frame::Heading(x) => *x >= level,
+                        Frame::Item(_) => true,
+                    })
+                    .unwrap_or(false)
+                {
+                    stack.pop();
+                }
+                (Frame::Heading(level), text)
+            } else if let Some((indent, text)) = parse_list_item(line) {
+                while stack
+                    .last()
+                    .map(|(x, _)| matches!(x, Frame::Item(x) if *x >= indent))
+                    .unwrap_or(false)
+                {
+                    stack.pop();
+                }
+                (Frame::Item(indent), text)
+            } else {
+                continue;
+            };
+            // add the node to its parent
+            let mut route = stack.last().map(|(_, x)| x.clone()).unwrap_or_default();
+            let parent = tree.root_mut().node_by_route_mut(&route).unwrap();
+            route.push(parent.children.len());
+            parent.add_child(Node::new(dotted_route(&route), text.to_string()));
+            stack.push((frame, route));
+        }
+        tree
+    }
+}
+
+impl<U: PartialEq, T: fmt::Display> Tree<U, T> {
+    /// Emit the [`Tree`] as a Markdown nested bullet list, indented by two spaces per level.
+    ///
+    /// The root represents the document, so only its descendants are written.
+    /// The list can be read back with [`Tree::from_markdown`].
+    pub fn to_markdown_list(&self) -> String {
+        /// Private recursive call for to_markdown_list
+        fn to_markdown_list_r<U, T: fmt::Display>(
+            node: &Node<U, T>,
+            level: usize,
+            markdown: &mut String,
+        ) {
+            markdown.push_str(&"  ".repeat(level));
+            markdown.push_str("- ");
+            markdown.push_str(&node.value.to_string());
+            markdown.push('\n');
+            node.children
+                .iter()
+                .for_each(|x| to_markdown_list_r(x, level + 1, markdown));
+        }
+        let mut markdown = String::new();
+        self.root()
+            .children
+            .iter()
+            .for_each(|x| to_markdown_list_r(x, 0, &mut markdown));
+        markdown
+    }
+}
+
+/// Parse an ATX heading, returning its level and text
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let line = strip_indentation(line, 3)?;
+    let level = line.chars().take_while(|x| *x == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = &line[level..];
+    if !text.is_empty() && !text.starts_with([' ', '\t']) {
+        return None;
+    }
+    // remove the optional closing sequence
+    let text = text.trim();
+    let text = match text.trim_end_matches('#') {
+        x if x.is_empty() || x.ends_with([' ', '\t']) => x.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+/// Parse a list item, returning the width of its indentation and its text
+fn parse_list_item(line: &str) -> Option<(usize, &str)> {
+    let mut indent = 0;
+    let content = line.trim_start_matches(|x| match x {
+        ' ' => {
+            indent += 1;
+            true
+        }
+        '\t' => {
+            indent += 4 - indent % 4;
+            true
+        }
+        _ => false,
+    });
+    let rest = if let Some(rest) = content.strip_prefix(['-', '*', '+']) {
+        rest
+    } else {
+        let digits = content.chars().take_while(|x| x.is_ascii_digit()).count();
+        if !(1..=9).contains(&digits) {
+            return None;
+        }
+        content[digits..].strip_prefix(['.', ')'])?
+    };
+    if rest.is_empty() {
+        Some((indent, rest))
+    } else if rest.starts_with([' ', '\t']) {
+        Some((indent, rest.trim()))
+    } else {
+        None
+    }
+}
+
+/// Strip up to `max` leading spaces; returns `None` if the line is indented more than that
+fn strip_indentation(line: &str, max: usize) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    (line.len() - trimmed.len() <= max).then_some(trimmed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_should_build_tree_from_markdown() {
+        let tree = Tree::from_markdown(
+            r#"# Title
+
+Some paragraph
+
+## Goals ##
+
+- fast
+  * really fast
+    1. faster than light
+- small
+
+```md
+# Not a heading
+- not an item
+```
+
+### Non goals
+
+#NotAHeading
+
+## Usage
+"#,
+        );
+        let values: Vec<(&str, &str)> = tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| (x.id().as_str(), x.value().as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("", ""),
+                ("1", "Title"),
+                ("1.1", "Goals"),
+                ("1.1.1", "fast"),
+                ("1.1.1.1", "really fast"),
+                ("1.1.1.1.1", "faster than light"),
+                ("1.1.2", "small"),
+                ("1.1.3", "Non goals"),
+                ("1.2", "Usage"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_convert_tree_to_markdown_list() {
+        let tree: Tree<&str, &str> = Tree::new(
+            Node::new("doc", "")
+                .with_child(
+                    Node::new("a", "A")
+                        .with_child(Node::new("a1", "A1"))
+                        .with_child(Node::new("a2", "A2").with_child(Node::new("a21", "A21"))),
+                )
+                .with_child(Node::new("b", "B")),
+        );
+        let markdown = tree.to_markdown_list();
+        assert_eq!(markdown, "- A\n  - A1\n  - A2\n    - A21\n- B\n");
+        // read back
+        let parsed = Tree::from_markdown(&markdown);
+        assert_eq!(parsed.root().count(), 6);
+        assert_eq!(parsed.to_markdown_list(), markdown);
+        assert_eq!(
+            parsed.root().node_by_route(&[0, 1, 0]).unwrap().id(),
+            "1.2.1"
+        );
+    }
+}
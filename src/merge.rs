@@ -0,0 +1,747 @@
+//! # Merge
+//!
+//! Three-way merge of trees keyed on node ids
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use crate::diff::{index, Index, Indexed};
+use crate::{DiffError, Node, Tree};
+
+/// One of the two sides merged by [`merge3`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// A change made by both sides which can't be merged automatically
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conflict<U, T> {
+    /// Both sides moved the node under different parents; `base` is `None` if both sides added the node
+    BothMoved {
+        id: U,
+        base: Option<U>,
+        ours: U,
+        theirs: U,
+    },
+    /// One side deleted the node, while the other side changed it, or added or moved nodes into its subtree.
+    /// `value` is the value of the node on the side which kept it
+    EditDelete { id: U, deleted_by: Side, value: T },
+    /// Both sides changed the value of the node; `base` is `None` if both sides added the node
+    BothChangedValue {
+        id: U,
+        base: Option<T>,
+        ours: T,
+        theirs: T,
+    },
+    /// Moves of both sides made these nodes ancestors of each other.
+    /// Cycles are never passed to the resolver: they're resolved by moving the nodes which can't be reached
+    /// from the root under their parent in ours, or in theirs if it was deleted, and always reported
+    /// in [`Merged::conflicts`]
+    Cycle { ids: Vec<U> },
+}
+
+/// How a [`Conflict`] is resolved by the resolver passed to [`merge3`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Resolution<T> {
+    /// Keep our version
+    Ours,
+    /// Keep their version
+    Theirs,
+    /// Keep the node with this value; moves are resolved as [`Resolution::Ours`]
+    Value(T),
+}
+
+/// The result of [`merge3`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Merged<U, T> {
+    pub tree: Tree<U, T>,
+    /// Conflicts which the resolver didn't resolve, and which have been resolved as [`Resolution::Ours`],
+    /// followed by the [`Conflict::Cycle`]s, which are never passed to the resolver and have been resolved
+    /// by moving nodes under their parent in ours, or in theirs
+    pub conflicts: Vec<Conflict<U, T>>,
+}
+
+/// A node of the merged tree
+struct State<'a, U, T> {
+    parent: Option<&'a U>,
+    value: T,
+}
+
+/// A node deleted by one side and kept by the other
+struct Deleted<'a, U, T> {
+    deleted_by: Side,
+    kept: &'a Indexed<'a, U, T>,
+}
+
+/// Merge `ours` and `theirs`, which both derive from `base`, keyed on node ids.
+///
+/// Changes made by only one side are applied; when both sides changed the same node in different ways,
+/// `resolver` is asked to resolve the [`Conflict`]. Conflicts for which it returns `None` are resolved
+/// as [`Resolution::Ours`] and reported in [`Merged::conflicts`], as are cycles, which the resolver isn't asked
+/// about.
+/// Children keep the order of the side which reordered them, with the nodes added by the other side
+/// placed after their previous sibling.
+/// Nodes whose merged parent was deleted, or is part of a cycle, are moved under their parent in ours,
+/// or in theirs; they're deleted only when both are deleted.
+///
+/// ```rust
+/// use orange_trees::{merge3, Node, Resolution, Tree};
+///
+/// let base: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 1)));
+/// let ours = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 2)).with_child(Node::new("/b", 3)));
+/// let theirs = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 4)).with_child(Node::new("/c", 5)));
+/// let merged = merge3(&base, &ours, &theirs, |_| Some(Resolution::Theirs)).unwrap();
+/// assert!(merged.conflicts.is_empty());
+/// assert_eq!(
+///     merged.tree,
+///     Tree::new(
+///         Node::new("/", 0)
+///             .with_child(Node::new("/a", 4))
+///             .with_child(Node::new("/b", 3))
+///             .with_child(Node::new("/c", 5))
+///     )
+/// );
+/// ```
+pub fn merge3<U, T, F>(
+    base: &Tree<U, T>,
+    ours: &Tree<U, T>,
+    theirs: &Tree<U, T>,
+    mut resolver: F,
+) -> Result<Merged<U, T>, DiffError<U>>
+where
+    U: Eq + Hash + Clone,
+    T: PartialEq + Clone,
+    F: FnMut(&Conflict<U, T>) -> Option<Resolution<T>>,
+{
+    if base.root().id != ours.root().id || base.root().id != theirs.root().id {
+        return Err(DiffError::RootMismatch);
+    }
+    let b = index(base)?;
+    let o = index(ours)?;
+    let t = index(theirs)?;
+    let mut conflicts = Vec::new();
+    let mut resolve = |conflict: Conflict<U, T>| match resolver(&conflict) {
+        Some(resolution) => resolution,
+        None => {
+            conflicts.push(conflict);
+            Resolution::Ours
+        }
+    };
+    // ids of the nodes of both sides
+    let mut ids: Vec<&U> = o.order.clone();
+    ids.extend(t.order.iter().filter(|x| !o.nodes.contains_key(*x)));
+    let mut merged: HashMap<&U, State<'_, U, T>> = HashMap::with_capacity(ids.len());
+    let mut deleted: HashMap<&U, Deleted<'_, U, T>> = HashMap::new();
+    for id in ids.iter().copied() {
+        let base = b.nodes.get(id);
+        match (o.nodes.get(id), t.nodes.get(id)) {
+            (Some(ours), Some(theirs)) => {
+                let base_value = base.map(|x| &x.node.value);
+                let value = match merge_values(base_value, &ours.node.value, &theirs.node.value) {
+                    Some(value) => value.clone(),
+                    None => match resolve(Conflict::BothChangedValue {
+                        id: id.clone(),
+                        base: base_value.cloned(),
+                        ours: ours.node.value.clone(),
+                        theirs: theirs.node.value.clone(),
+                    }) {
+                        Resolution::Ours => ours.node.value.clone(),
+                        Resolution::Theirs => theirs.node.value.clone(),
+                        Resolution::Value(value) => value,
+                    },
+                };
+                let base_parent = base.map(|x| x.parent);
+                let parent = match merge_values(base_parent.as_ref(), &ours.parent, &theirs.parent)
+                {
+                    Some(parent) => *parent,
+                    None => match resolve(Conflict::BothMoved {
+                        id: id.clone(),
+                        base: base_parent.flatten().cloned(),
+                        ours: ours.parent.unwrap().clone(),
+                        theirs: theirs.parent.unwrap().clone(),
+                    }) {
+                        Resolution::Theirs => theirs.parent,
+                        _ => ours.parent,
+                    },
+                };
+                merged.insert(id, State { parent, value });
+            }
+            (Some(kept), None) | (None, Some(kept)) => {
+                let kept_by = match o.nodes.contains_key(id) {
+                    true => Side::Ours,
+                    false => Side::Theirs,
+                };
+                match base {
+                    // added by one side
+                    None => {
+                        merged.insert(
+                            id,
+                            State {
+                                parent: kept.parent,
+                                value: kept.node.value.clone(),
+                            },
+                        );
+                    }
+                    // deleted by the other side
+                    Some(base) => {
+                        let deleted_by = match kept_by {
+                            Side::Ours => Side::Theirs,
+                            Side::Theirs => Side::Ours,
+                        };
+                        if base.node.value == kept.node.value && base.parent == kept.parent {
+                            deleted.insert(id, Deleted { deleted_by, kept });
+                            continue;
+                        }
+                        if let Some(value) = resolve_edit_delete(&mut resolve, id, deleted_by, kept)
+                        {
+                            merged.insert(
+                                id,
+                                State {
+                                    parent: kept.parent,
+                                    value,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    // deleted parents into which the other side moved or added nodes
+    let mut settled: HashSet<&U> = HashSet::new();
+    let mut pending: Vec<&U> = ids
+        .iter()
+        .rev()
+        .copied()
+        .filter(|x| merged.contains_key(*x))
+        .collect();
+    while let Some(id) = pending.pop() {
+        for parent in deleted_parents(id, merged[id].parent, &b, &o, &t, &deleted) {
+            if !settled.insert(parent) {
+                continue;
+            }
+            let Deleted { deleted_by, kept } = &deleted[parent];
+            if let Some(value) = resolve_edit_delete(&mut resolve, parent, *deleted_by, kept) {
+                merged.insert(
+                    parent,
+                    State {
+                        parent: kept.parent,
+                        value,
+                    },
+                );
+                pending.push(parent);
+            }
+        }
+    }
+    // cycles made by moves of both sides
+    for cycle in find_cycles(&ids, &merged) {
+        conflicts.push(Conflict::Cycle {
+            ids: cycle.into_iter().cloned().collect(),
+        });
+    }
+    let parents = attach_nodes(&base.root().id, &ids, &merged, &o, &t);
+    let mut children: HashMap<&U, Vec<&U>> = HashMap::new();
+    for id in ids.iter().copied() {
+        if let Some(parent) = parents.get(id) {
+            children.entry(*parent).or_default().push(id);
+        }
+    }
+    let children: HashMap<&U, Vec<&U>> = children
+        .into_iter()
+        .map(|(parent, ids)| (parent, sort_children(parent, ids, &b, &o, &t)))
+        .collect();
+    let root = build_node(&base.root().id, &mut merged, &children);
+    Ok(Merged {
+        tree: Tree::new(root),
+        conflicts,
+    })
+}
+
+/// Merge a property of a node, returning `None` if both sides changed it in different ways
+fn merge_values<'a, V: PartialEq>(base: Option<&V>, ours: &'a V, theirs: &'a V) -> Option<&'a V> {
+    if ours == theirs || base == Some(theirs) {
+        Some(ours)
+    } else if base == Some(ours) {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+/// Resolve the deletion of a node kept by one side, returning its value if it must be kept
+fn resolve_edit_delete<U, T, R>(
+    resolve: &mut R,
+    id: &U,
+    deleted_by: Side,
+    kept: &Indexed<'_, U, T>,
+) -> Option<T>
+where
+    U: Clone,
+    T: Clone,
+    R: FnMut(Conflict<U, T>) -> Resolution<T>,
+{
+    match resolve(Conflict::EditDelete {
+        id: id.clone(),
+        deleted_by,
+        value: kept.node.value.clone(),
+    }) {
+        Resolution::Value(value) => Some(value),
+        Resolution::Ours if deleted_by == Side::Theirs => Some(kept.node.value.clone()),
+        Resolution::Theirs if deleted_by == Side::Ours => Some(kept.node.value.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the parents of `id` deleted by one side, into which the other side moved or added the node,
+/// or which are its merged `parent`
+fn deleted_parents<'a, U, T>(
+    id: &U,
+    parent: Option<&'a U>,
+    base: &Index<'a, U, T>,
+    ours: &Index<'a, U, T>,
+    theirs: &Index<'a, U, T>,
+    deleted: &HashMap<&'a U, Deleted<'a, U, T>>,
+) -> Vec<&'a U>
+where
+    U: Eq + Hash,
+{
+    let base_parent = base.nodes.get(id).and_then(|x| x.parent);
+    [(Side::Ours, ours), (Side::Theirs, theirs)]
+        .into_iter()
+        .filter_map(|(side, index)| {
+            let candidate = index.nodes.get(id).and_then(|x| x.parent)?;
+            let moved_into = base_parent != Some(candidate);
+            deleted
+                .get(candidate)
+                .filter(|x| x.deleted_by != side && (moved_into || parent == Some(candidate)))
+                .map(|_| candidate)
+        })
+        .collect()
+}
+
+/// Find the cycles of merged parents, returning the nodes of each cycle
+fn find_cycles<'a, U, T>(ids: &[&'a U], merged: &HashMap<&'a U, State<'a, U, T>>) -> Vec<Vec<&'a U>>
+where
+    U: Eq + Hash,
+{
+    let mut visited: HashSet<&U> = HashSet::new();
+    let mut cycles = Vec::new();
+    for id in ids.iter().copied().filter(|x| merged.contains_key(*x)) {
+        // position of each node in the path from `id`
+        let mut path: HashMap<&U, usize> = HashMap::new();
+        let mut nodes: Vec<&U> = Vec::new();
+        let mut current = Some(id);
+        while let Some(node) = current {
+            if visited.contains(node) {
+                break;
+            }
+            if let Some(start) = path.get(node) {
+                cycles.push(nodes[*start..].to_vec());
+                break;
+            }
+            path.insert(node, nodes.len());
+            nodes.push(node);
+            current = merged.get(node).and_then(|x| x.parent);
+        }
+        visited.extend(nodes);
+    }
+    cycles
+}
+
+/// Attach the merged nodes to the tree from `root`, returning the parent of each attached node.
+///
+/// Nodes are attached under their merged parent; nodes which can't be reached from the root that way,
+/// because their merged parent was deleted or is part of a cycle, are attached under their parent in ours,
+/// or in theirs. Nodes whose parents are all deleted are left out
+fn attach_nodes<'a, U, T>(
+    root: &'a U,
+    ids: &[&'a U],
+    merged: &HashMap<&'a U, State<'a, U, T>>,
+    ours: &Index<'a, U, T>,
+    theirs: &Index<'a, U, T>,
+) -> HashMap<&'a U, &'a U>
+where
+    U: Eq + Hash,
+{
+    // nodes waiting for each parent, for the merged parents, our parents and their parents
+    let mut waiting: [HashMap<&U, Vec<&U>>; 3] = Default::default();
+    for id in ids.iter().copied().filter(|x| merged.contains_key(*x)) {
+        let candidates = [
+            merged[id].parent,
+            ours.nodes.get(id).and_then(|x| x.parent),
+            theirs.nodes.get(id).and_then(|x| x.parent),
+        ];
+        for (waiting, parent) in waiting.iter_mut().zip(candidates) {
+            if let Some(parent) = parent {
+                waiting.entry(parent).or_default().push(id);
+            }
+        }
+    }
+    let mut parents: HashMap<&U, &U> = HashMap::new();
+    let mut attached: HashSet<&U> = HashSet::from([root]);
+    let mut queue: VecDeque<&U> = VecDeque::from([root]);
+    // nodes which can be attached under our parents and under their parents
+    let mut fallback: [VecDeque<(&U, &U)>; 2] = Default::default();
+    loop {
+        while let Some(parent) = queue.pop_front() {
+            for child in waiting[0].get(parent).into_iter().flatten().copied() {
+                if attached.insert(child) {
+                    parents.insert(child, parent);
+                    queue.push_back(child);
+                }
+            }
+            for (fallback, waiting) in fallback.iter_mut().zip(&waiting[1..]) {
+                fallback.extend(
+                    waiting
+                        .get(parent)
+                        .into_iter()
+                        .flatten()
+                        .map(|child| (*child, parent)),
+                );
+            }
+        }
+        let next = fallback.iter_mut().find_map(|fallback| {
+            std::iter::from_fn(|| fallback.pop_front()).find(|(child, _)| !attached.contains(child))
+        });
+        let Some((child, parent)) = next else { break };
+        attached.insert(child);
+        parents.insert(child, parent);
+        queue.push_back(child);
+    }
+    parents
+}
+
+/// Sort the merged children of `parent`, following the order of the side which reordered them
+fn sort_children<'a, U, T>(
+    parent: &U,
+    mut ids: Vec<&'a U>,
+    base: &Index<'a, U, T>,
+    ours: &Index<'a, U, T>,
+    theirs: &Index<'a, U, T>,
+) -> Vec<&'a U>
+where
+    U: Eq + Hash,
+{
+    let order = |index: &Index<'a, U, T>| -> Vec<&'a U> {
+        index
+            .nodes
+            .get(parent)
+            .map(|x| x.node.children.iter().map(|x| &x.id).collect())
+            .unwrap_or_default()
+    };
+    let common = |a: &[&'a U], b: &[&'a U]| -> Vec<&'a U> {
+        a.iter().filter(|x| b.contains(x)).copied().collect()
+    };
+    let (base_order, ours_order, theirs_order) = (order(base), order(ours), order(theirs));
+    let ours_reordered = common(&ours_order, &base_order) != common(&base_order, &ours_order);
+    let (primary, secondary) = match ours_reordered {
+        true => (ours_order, theirs_order),
+        false => (theirs_order, ours_order),
+    };
+    let mut sorted: Vec<&U> = primary.into_iter().filter(|x| ids.contains(x)).collect();
+    let mut previous: Option<&U> = None;
+    for id in secondary.into_iter().filter(|x| ids.contains(x)) {
+        if !sorted.contains(&id) {
+            let index = previous
+                .and_then(|x| sorted.iter().position(|y| *y == x))
+                .map(|x| x + 1)
+                .unwrap_or(0);
+            sorted.insert(index, id);
+        }
+        previous = Some(id);
+    }
+    ids.retain(|x| !sorted.contains(x));
+    sorted.extend(ids);
+    sorted
+}
+
+/// Build the merged node `id` with its descendants
+fn build_node<U, T>(
+    id: &U,
+    merged: &mut HashMap<&U, State<'_, U, T>>,
+    children: &HashMap<&U, Vec<&U>>,
+) -> Node<U, T>
+where
+    U: Eq + Hash + Clone,
+{
+    let state = merged.remove(id).unwrap();
+    let mut node = Node::new(id.clone(), state.value);
+    for child in children.get(id).into_iter().flatten() {
+        node.children.push(build_node(child, merged, children));
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn base() -> Tree<&'static str, usize> {
+        Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 1)
+                        .with_child(Node::new("/bin/ls", 2))
+                        .with_child(Node::new("/bin/pwd", 3)),
+                )
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/a", 5))
+                        .with_child(Node::new("/tmp/b", 6)),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_should_merge_non_overlapping_changes() {
+        let ours = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 10)
+                        .with_child(Node::new("/bin/pwd", 3))
+                        .with_child(Node::new("/bin/ls", 2)),
+                )
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/a", 5))
+                        .with_child(Node::new("/tmp/b", 6))
+                        .with_child(Node::new("/tmp/c", 7)),
+                ),
+        );
+        let theirs = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 1)
+                        .with_child(Node::new("/bin/sh", 8))
+                        .with_child(Node::new("/bin/ls", 2))
+                        .with_child(Node::new("/bin/pwd", 30))
+                        .with_child(Node::new("/tmp/a", 5)),
+                )
+                .with_child(Node::new("/tmp", 4)),
+        );
+        let merged = merge3(&base(), &ours, &theirs, |_| None).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert_eq!(
+            merged.tree,
+            Tree::new(
+                Node::new("/", 0)
+                    .with_child(
+                        Node::new("/bin", 10)
+                            .with_child(Node::new("/bin/sh", 8))
+                            .with_child(Node::new("/bin/pwd", 30))
+                            .with_child(Node::new("/tmp/a", 5))
+                            .with_child(Node::new("/bin/ls", 2)),
+                    )
+                    .with_child(Node::new("/tmp", 4).with_child(Node::new("/tmp/c", 7))),
+            )
+        );
+    }
+
+    #[test]
+    fn test_should_report_conflicts() {
+        let ours = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 10)
+                        .with_child(Node::new("/bin/ls", 2))
+                        .with_child(Node::new("/tmp/a", 5)),
+                )
+                .with_child(Node::new("/tmp", 4).with_child(Node::new("/tmp/b", 6))),
+        );
+        let theirs = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 20)
+                        .with_child(Node::new("/bin/ls", 2))
+                        .with_child(Node::new("/bin/pwd", 30)),
+                )
+                .with_child(Node::new("/tmp/a", 5)),
+        );
+        let merged = merge3(&base(), &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![
+                Conflict::BothChangedValue {
+                    id: "/bin",
+                    base: Some(1),
+                    ours: 10,
+                    theirs: 20
+                },
+                Conflict::BothMoved {
+                    id: "/tmp/a",
+                    base: Some("/tmp"),
+                    ours: "/bin",
+                    theirs: "/"
+                },
+                Conflict::EditDelete {
+                    id: "/bin/pwd",
+                    deleted_by: Side::Ours,
+                    value: 30
+                },
+            ]
+        );
+        // unresolved conflicts keep our version; /tmp is deleted by theirs and unchanged by ours
+        let mut expected = ours.clone();
+        expected.root_mut().remove_child(&"/tmp");
+        assert_eq!(merged.tree, expected);
+        // resolve everything as theirs
+        let merged = merge3(&base(), &ours, &theirs, |_| Some(Resolution::Theirs)).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert_eq!(merged.tree, theirs);
+    }
+
+    #[test]
+    fn test_should_merge_deleted_parents_and_cycles() {
+        // ours deletes /tmp, while theirs adds a child to it
+        let ours = Tree::new(Node::new("/", 0).with_child(base().root().children()[0].clone()));
+        let mut theirs = base();
+        theirs
+            .root_mut()
+            .query_mut(&"/tmp")
+            .unwrap()
+            .add_child(Node::new("/tmp/c", 7));
+        let merged = merge3(&base(), &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict::EditDelete {
+                id: "/tmp",
+                deleted_by: Side::Ours,
+                value: 4
+            }]
+        );
+        assert_eq!(merged.tree, ours);
+        let merged = merge3(&base(), &ours, &theirs, |_| Some(Resolution::Theirs)).unwrap();
+        let mut expected = theirs.clone();
+        expected
+            .root_mut()
+            .query_mut(&"/tmp")
+            .unwrap()
+            .retain_recursive(&|x| *x.id() == "/tmp/c");
+        assert_eq!(merged.tree, expected);
+        // ours moves /tmp under /bin, theirs moves /bin under /tmp
+        let ours = Tree::new(
+            Node::new("/", 0).with_child(
+                base().root().children()[0]
+                    .clone()
+                    .with_child(base().root().children()[1].clone()),
+            ),
+        );
+        let theirs = Tree::new(
+            Node::new("/", 0).with_child(
+                base().root().children()[1]
+                    .clone()
+                    .with_child(base().root().children()[0].clone()),
+            ),
+        );
+        let merged = merge3(&base(), &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict::Cycle {
+                ids: vec!["/bin", "/tmp"]
+            }]
+        );
+        assert_eq!(merged.tree, ours);
+    }
+
+    #[test]
+    fn test_should_keep_nodes_moved_out_of_deleted_parents() {
+        // ours moves /b under /p/a; theirs deletes /p and moves /p/a under /b
+        let base: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("/p", 1).with_child(Node::new("/p/a", 2)))
+                .with_child(Node::new("/b", 3)),
+        );
+        let ours = Tree::new(Node::new("/", 0).with_child(
+            Node::new("/p", 1).with_child(Node::new("/p/a", 2).with_child(Node::new("/b", 3))),
+        ));
+        let theirs = Tree::new(
+            Node::new("/", 0).with_child(Node::new("/b", 3).with_child(Node::new("/p/a", 2))),
+        );
+        let merged = merge3(&base, &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict::Cycle {
+                ids: vec!["/p/a", "/b"]
+            }]
+        );
+        assert_eq!(merged.tree, theirs);
+        // theirs deletes /p, which ours changed, and moves /a under it; ours keeps /a at the root
+        let base: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("/p", 1))
+                .with_child(Node::new("/a", 2)),
+        );
+        let ours = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 2)));
+        let theirs = Tree::new(
+            Node::new("/", 0).with_child(Node::new("/p", 10).with_child(Node::new("/a", 2))),
+        );
+        let merged = merge3(&base, &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict::EditDelete {
+                id: "/p",
+                deleted_by: Side::Ours,
+                value: 10
+            }]
+        );
+        assert_eq!(merged.tree, ours);
+        let merged = merge3(&base, &ours, &theirs, |_| Some(Resolution::Theirs)).unwrap();
+        assert_eq!(merged.tree, theirs);
+    }
+
+    #[test]
+    fn test_should_report_nodes_moved_into_deleted_parents() {
+        // ours deletes /p and moves /q/a to the root; theirs moves /q/a under /p
+        let base: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("/p", 1))
+                .with_child(Node::new("/q", 2).with_child(Node::new("/q/a", 3))),
+        );
+        let ours = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("/q", 2))
+                .with_child(Node::new("/q/a", 3)),
+        );
+        let theirs = Tree::new(
+            Node::new("/", 0)
+                .with_child(Node::new("/p", 1).with_child(Node::new("/q/a", 3)))
+                .with_child(Node::new("/q", 2)),
+        );
+        let merged = merge3(&base, &ours, &theirs, |_| None).unwrap();
+        assert_eq!(
+            merged.conflicts,
+            vec![
+                Conflict::BothMoved {
+                    id: "/q/a",
+                    base: Some("/q"),
+                    ours: "/",
+                    theirs: "/p"
+                },
+                Conflict::EditDelete {
+                    id: "/p",
+                    deleted_by: Side::Ours,
+                    value: 1
+                },
+            ]
+        );
+        assert_eq!(merged.tree, ours);
+        let merged = merge3(&base, &ours, &theirs, |_| Some(Resolution::Theirs)).unwrap();
+        assert_eq!(merged.conflicts, vec![]);
+        assert_eq!(merged.tree, theirs);
+    }
+
+    #[test]
+    fn test_should_report_merge_errors() {
+        let other: Tree<&'static str, usize> = Tree::new(Node::new("root", 0));
+        assert_eq!(
+            merge3(&base(), &base(), &other, |_| None).unwrap_err(),
+            DiffError::RootMismatch
+        );
+    }
+}
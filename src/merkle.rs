@@ -0,0 +1,243 @@
+//! # Merkle
+//!
+//! Hash subtrees to detect which branches changed between two snapshots
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::{Node, Tree};
+
+/// Hashes of a node, computed by [`Node::merkle`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MerkleHash {
+    /// Hash of the id and the value of the node
+    pub local: u64,
+    /// Hash of `local` and of the subtree hashes of the children, in order
+    pub subtree: u64,
+}
+
+/// A change found by [`changed_subtrees`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SubtreeChange<U> {
+    /// The node has been added, with its subtree
+    Added(U),
+    /// The node has been removed, with its subtree
+    Removed(U),
+    /// The value of the node or the order of its children changed
+    Modified(U),
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Returns a node shaped like this [`Node`], where values are the [`MerkleHash`]es of the nodes.
+    ///
+    /// Hashers are built with `hasher`, which must build identical hashers to get comparable hashes
+    /// across calls, so a fresh `RandomState` can't be used for each call
+    pub fn merkle<S: BuildHasher>(&self, hasher: &S) -> Node<U, MerkleHash>
+    where
+        U: Hash + Clone,
+        T: Hash,
+    {
+        let mut state = hasher.build_hasher();
+        self.id.hash(&mut state);
+        self.value.hash(&mut state);
+        let local = state.finish();
+        let children: Vec<Node<U, MerkleHash>> =
+            self.children.iter().map(|x| x.merkle(hasher)).collect();
+        let mut state = hasher.build_hasher();
+        state.write_u64(local);
+        children
+            .iter()
+            .for_each(|x| state.write_u64(x.value.subtree));
+        Node {
+            id: self.id.clone(),
+            value: MerkleHash {
+                local,
+                subtree: state.finish(),
+            },
+            children,
+        }
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Returns a [`Tree`] shaped like this one, where values are the [`MerkleHash`]es of the nodes.
+    /// See [`Node::merkle`]
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    /// let tree: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 1)));
+    /// let mut other = tree.clone();
+    /// other.root_mut().query_mut(&"/a").unwrap().set_value(2);
+    /// let (a, b) = (tree.merkle(&hasher), other.merkle(&hasher));
+    /// assert_ne!(a.root().value().subtree, b.root().value().subtree);
+    /// assert_eq!(a.root().value().local, b.root().value().local);
+    /// ```
+    pub fn merkle<S: BuildHasher>(&self, hasher: &S) -> Tree<U, MerkleHash>
+    where
+        U: Hash + Clone,
+        T: Hash,
+    {
+        Tree::new(self.root.merkle(hasher))
+    }
+}
+
+/// Compare the hashes of an old snapshot, computed by [`Tree::merkle`] with the same `hasher`, with `new`,
+/// returning the topmost changes in pre-order.
+///
+/// Children are matched by id with their siblings. Only the branches whose subtree hash differs are visited,
+/// and the subtrees of added and removed nodes are not reported; moving a node to another parent is reported
+/// as a removal and an addition.
+///
+/// ```rust
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::BuildHasherDefault;
+///
+/// use orange_trees::{changed_subtrees, Node, SubtreeChange, Tree};
+///
+/// let hasher = BuildHasherDefault::<DefaultHasher>::default();
+/// let old: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 1)));
+/// let hashes = old.merkle(&hasher);
+/// let new: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/b", 1)));
+/// assert_eq!(
+///     changed_subtrees(&hashes, &new, &hasher),
+///     vec![SubtreeChange::Removed("/a"), SubtreeChange::Added("/b")]
+/// );
+/// ```
+pub fn changed_subtrees<U, T, S>(
+    old: &Tree<U, MerkleHash>,
+    new: &Tree<U, T>,
+    hasher: &S,
+) -> Vec<SubtreeChange<U>>
+where
+    U: Eq + Hash + Clone,
+    T: Hash,
+    S: BuildHasher,
+{
+    /// Private recursive call for changed_subtrees
+    fn changed_subtrees_r<U: Eq + Hash + Clone>(
+        old: &Node<U, MerkleHash>,
+        new: &Node<U, MerkleHash>,
+        changes: &mut Vec<SubtreeChange<U>>,
+    ) {
+        if old.value.subtree == new.value.subtree {
+            return;
+        }
+        let old_children: HashMap<&U, &Node<U, MerkleHash>> =
+            old.children.iter().map(|x| (&x.id, x)).collect();
+        let order_changed = old
+            .children
+            .iter()
+            .map(|x| &x.id)
+            .filter(|x| new.children.iter().any(|y| &y.id == *x))
+            .ne(new
+                .children
+                .iter()
+                .map(|x| &x.id)
+                .filter(|x| old_children.contains_key(x)));
+        if old.value.local != new.value.local || order_changed {
+            changes.push(SubtreeChange::Modified(new.id.clone()));
+        }
+        for child in old.children.iter() {
+            if !new.children.iter().any(|x| x.id == child.id) {
+                changes.push(SubtreeChange::Removed(child.id.clone()));
+            }
+        }
+        for child in new.children.iter() {
+            match old_children.get(&child.id) {
+                Some(old) => changed_subtrees_r(old, child, changes),
+                None => changes.push(SubtreeChange::Added(child.id.clone())),
+            }
+        }
+    }
+    let new = new.root().merkle(hasher);
+    if old.root().id != new.id {
+        return vec![
+            SubtreeChange::Removed(old.root().id.clone()),
+            SubtreeChange::Added(new.id.clone()),
+        ];
+    }
+    let mut changes = Vec::new();
+    changed_subtrees_r(old.root(), &new, &mut changes);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_compute_merkle_hashes() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let hashes = tree().merkle(&hasher);
+        assert_eq!(hashes, tree().merkle(&hasher));
+        assert_eq!(hashes.root().count(), tree().root().count());
+        // changing a leaf changes the hashes of its ancestors only
+        let mut other = tree();
+        other
+            .root_mut()
+            .query_mut(&"/tmp/a/b")
+            .unwrap()
+            .set_value(7);
+        let other = other.merkle(&hasher);
+        for id in ["/", "/tmp", "/tmp/a", "/tmp/a/b"] {
+            assert_ne!(
+                hashes.root().query(&id).unwrap().value().subtree,
+                other.root().query(&id).unwrap().value().subtree
+            );
+        }
+        assert_eq!(
+            hashes.root().query(&"/tmp/a").unwrap().value().local,
+            other.root().query(&"/tmp/a").unwrap().value().local
+        );
+        assert_eq!(
+            hashes.root().query(&"/bin").unwrap().value(),
+            other.root().query(&"/bin").unwrap().value()
+        );
+    }
+
+    #[test]
+    fn test_should_find_changed_subtrees() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let hashes = tree().merkle(&hasher);
+        assert_eq!(changed_subtrees(&hashes, &tree(), &hasher), vec![]);
+        let new: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 0)
+                .with_child(
+                    Node::new("/bin", 1)
+                        .with_child(Node::new("/bin/pwd", 3))
+                        .with_child(Node::new("/bin/ls", 2)),
+                )
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/a", 9))
+                        .with_child(Node::new("/tmp/c", 7).with_child(Node::new("/tmp/c/d", 8))),
+                ),
+        );
+        assert_eq!(
+            changed_subtrees(&hashes, &new, &hasher),
+            vec![
+                SubtreeChange::Modified("/bin"),
+                SubtreeChange::Modified("/tmp/a"),
+                SubtreeChange::Removed("/tmp/a/b"),
+                SubtreeChange::Added("/tmp/c"),
+            ]
+        );
+        let other: Tree<&'static str, usize> = Tree::new(Node::new("root", 0));
+        assert_eq!(
+            changed_subtrees(&hashes, &other, &hasher),
+            vec![SubtreeChange::Removed("/"), SubtreeChange::Added("root")]
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! # Newick
+//!
+//! Read and write trees in the Newick format (e.g. `(A:0.1,B:0.2)C;`)
+
+use std::fmt;
+
+use crate::{dotted_route, Node, Tree};
+
+/// Characters which can't appear in an unquoted label
+const RESERVED: &[char] = &['(', ')', '[', ']', '\'', ':', ';', ','];
+
+/// The value of a node in a Newick tree
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewickValue {
+    /// The node label, if any
+    pub name: Option<String>,
+    /// The length of the branch to the parent, if any
+    pub length: Option<f64>,
+}
+
+impl NewickValue {
+    /// Instantiates a new [`NewickValue`]
+    pub fn new(name: Option<String>, length: Option<f64>) -> Self {
+        Self { name, length }
+    }
+}
+
+/// Describes an error which occurred while parsing a Newick tree
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NewickError {
+    /// The input ended before the tree was complete
+    UnexpectedEnd,
+    /// Found an unexpected character at `position` (counted in characters)
+    UnexpectedChar { position: usize, found: char },
+    /// The branch length at `position` is not a number
+    InvalidLength { position: usize },
+    /// The node at `position` has the same id as one of its siblings
+    DuplicateId { position: usize },
+}
+
+impl fmt::Display for NewickError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::UnexpectedChar { position, found } => {
+                write!(f, "unexpected character '{found}' at position {position}")
+            }
+            Self::InvalidLength { position } => {
+                write!(f, "invalid branch length at position {position}")
+            }
+            Self::DuplicateId { position } => write!(f, "duplicate id at position {position}"),
+        }
+    }
+}
+
+impl std::error::Error for NewickError {}
+
+impl Tree<String, NewickValue> {
+    /// Parse a [`Tree`] from a Newick string.
+    ///
+    /// Each node has its position as id (e.g. `1.3.2`, where `1` is the first child of the root; the root id is empty).
+    /// Use [`Tree::parse_newick`] to derive ids and values differently.
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:0.1,'B b':0.2,(C,D)E)F;").unwrap();
+    /// assert_eq!(tree.root().value().name.as_deref(), Some("F"));
+    /// assert_eq!(tree.root().count(), 6);
+    /// let b = tree.root().query(&"2".to_string()).unwrap().value();
+    /// assert_eq!(b.name.as_deref(), Some("B b"));
+    /// assert_eq!(b.length, Some(0.2));
+    /// ```
+    pub fn from_newick(text: &str) -> Result<Self, NewickError> {
+        Self::parse_newick(text, |route, _| dotted_route(route), |x| x)
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Parse a [`Tree`] from a Newick string.
+    ///
+    /// `id_fn` is called with the route and the value of each node to get its id;
+    /// `value_fn` converts the [`NewickValue`] into the node value. Siblings must have different ids.
+    pub fn parse_newick<I, V>(
+        text: &str,
+        mut id_fn: I,
+        mut value_fn: V,
+    ) -> Result<Self, NewickError>
+    where
+        I: FnMut(&[usize], &NewickValue) -> U,
+        V: FnMut(NewickValue) -> T,
+    {
+        let mut parser = Parser {
+            input: text.chars().collect(),
+            position: 0,
+        };
+        let mut route = Vec::new();
+        let root = parser.subtree(&mut route, &mut id_fn, &mut value_fn)?;
+        parser.expect(';')?;
+        match parser.peek() {
+            None => Ok(Tree::new(root)),
+            Some(found) => Err(NewickError::UnexpectedChar {
+                position: parser.position,
+                found,
+            }),
+        }
+    }
+}
+
+impl<U: PartialEq> Tree<U, NewickValue> {
+    /// Write the [`Tree`] as a Newick string
+    pub fn to_newick(&self) -> String {
+        self.to_newick_with(|x| x.value().clone())
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Write the [`Tree`] as a Newick string, getting the label and the branch length of each node from `f`.
+    ///
+    /// Labels are quoted whenever they contain whitespace, underscores or reserved characters.
+    pub fn to_newick_with<F>(&self, f: F) -> String
+    where
+        F: Fn(&Node<U, T>) -> NewickValue,
+    {
+        /// Private recursive call for to_newick_with
+        fn to_newick_r<U, T, F>(node: &Node<U, T>, f: &F, newick: &mut String)
+        where
+            F: Fn(&Node<U, T>) -> NewickValue,
+        {
+            if !node.children.is_empty() {
+                newick.push('(');
+                for (i, child) in node.children.iter().enumerate() {
+                    if i > 0 {
+                        newick.push(',');
+                    }
+                    to_newick_r(child, f, newick);
+                }
+                newick.push(')');
+            }
+            let value = f(node);
+            if let Some(name) = value.name {
+                write_label(&name, newick);
+            }
+            if let Some(length) = value.length {
+                newick.push(':');
+                newick.push_str(&length.to_string());
+            }
+        }
+        let mut newick = String::new();
+        to_newick_r(self.root(), &f, &mut newick);
+        newick.push(';');
+        newick
+    }
+}
+
+/// Write a label, quoting it if required
+fn write_label(name: &str, newick: &mut String) {
+    if !name.is_empty()
+        && !name
+            .chars()
+            .any(|x| x.is_whitespace() || x == '_' || RESERVED.contains(&x))
+    {
+        newick.push_str(name);
+    } else {
+        newick.push('\'');
+        newick.push_str(&name.replace('\'', "''"));
+        newick.push('\'');
+    }
+}
+
+/// Recursive descent parser for Newick strings
+struct Parser {
+    input: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    /// Parse a node with its descendants. `route` is the route of the node
+    fn subtree<U, T, I, V>(
+        &mut self,
+        route: &mut Vec<usize>,
+        id_fn: &mut I,
+        value_fn: &mut V,
+    ) -> Result<Node<U, T>, NewickError>
+    where
+        U: PartialEq,
+        I: FnMut(&[usize], &NewickValue) -> U,
+        V: FnMut(NewickValue) -> T,
+    {
+        let mut children: Vec<Node<U, T>> = Vec::new();
+        if self.peek() == Some('(') {
+            self.position += 1;
+            loop {
+                self.skip_blanks()?;
+                let position = self.position;
+                route.push(children.len());
+                let child = self.subtree(route, id_fn, value_fn)?;
+                route.pop();
+                if children.iter().any(|x| x.id == child.id) {
+                    return Err(NewickError::DuplicateId { position });
+                }
+                children.push(child);
+                match self.next()? {
+                    ',' => continue,
+                    ')' => break,
+                    found => {
+                        return Err(NewickError::UnexpectedChar {
+                            position: self.position - 1,
+                            found,
+                        })
+                    }
+                }
+            }
+        }
+        let name = self.label()?;
+        let length = if self.peek() == Some(':') {
+            self.position += 1;
+            Some(self.length()?)
+        } else {
+            None
+        };
+        let value = NewickValue::new(name, length);
+        let mut node = Node::new(id_fn(route, &value), value_fn(value));
+        node.children = children;
+        Ok(node)
+    }
+
+    /// Parse an optional label
+    fn label(&mut self) -> Result<Option<String>, NewickError> {
+        if self.peek() == Some('\'') {
+            self.position += 1;
+            let mut name = String::new();
+            loop {
+                match self.input.get(self.position) {
+                    None => return Err(NewickError::UnexpectedEnd),
+                    Some('\'') if self.input.get(self.position + 1) == Some(&'\'') => {
+                        name.push('\'');
+                        self.position += 2;
+                    }
+                    Some('\'') => {
+                        self.position += 1;
+                        return Ok(Some(name));
+                    }
+                    Some(x) => {
+                        name.push(*x);
+                        self.position += 1;
+                    }
+                }
+            }
+        }
+        let name: String = self
+            .take_while(|x| !x.is_whitespace() && !RESERVED.contains(&x))
+            .replace('_', " ");
+        Ok((!name.is_empty()).then_some(name))
+    }
+
+    /// Parse a branch length
+    fn length(&mut self) -> Result<f64, NewickError> {
+        self.skip_blanks()?;
+        let position = self.position;
+        self.take_while(|x| !x.is_whitespace() && !RESERVED.contains(&x))
+            .parse()
+            .map_err(|_| NewickError::InvalidLength { position })
+    }
+
+    /// Consume the next character, which must be `expected`
+    fn expect(&mut self, expected: char) -> Result<(), NewickError> {
+        match self.next()? {
+            x if x == expected => Ok(()),
+            found => Err(NewickError::UnexpectedChar {
+                position: self.position - 1,
+                found,
+            }),
+        }
+    }
+
+    /// Consume and return the next character, skipping blanks
+    fn next(&mut self) -> Result<char, NewickError> {
+        let next = self.peek().ok_or(NewickError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(next)
+    }
+
+    /// Return the next character, skipping blanks. Returns `None` at the end of the input
+    fn peek(&mut self) -> Option<char> {
+        self.skip_blanks().ok()?;
+        self.input.get(self.position).copied()
+    }
+
+    /// Skip whitespace and comments
+    fn skip_blanks(&mut self) -> Result<(), NewickError> {
+        loop {
+            match self.input.get(self.position) {
+                Some(x) if x.is_whitespace() => self.position += 1,
+                Some('[') => {
+                    let end = self.input[self.position..]
+                        .iter()
+                        .position(|x| *x == ']')
+                        .ok_or(NewickError::UnexpectedEnd)?;
+                    self.position += end + 1;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Consume characters while `predicate` holds
+    fn take_while<P>(&mut self, predicate: P) -> String
+    where
+        P: Fn(char) -> bool,
+    {
+        let start = self.position;
+        while self
+            .input
+            .get(self.position)
+            .map(|x| predicate(*x))
+            .unwrap_or(false)
+        {
+            self.position += 1;
+        }
+        self.input[start..self.position].iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_should_parse_newick() {
+        let tree = Tree::from_newick(
+            "((Homo_sapiens:0.1, 'Pan troglodytes':0.2 [chimp])Hominini:1e-1,'O''Brien',):0;\n",
+        )
+        .unwrap();
+        let nodes: Vec<(&str, NewickValue)> = tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| (x.id().as_str(), x.value().clone()))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                ("", NewickValue::new(None, Some(0.0))),
+                (
+                    "1",
+                    NewickValue::new(Some("Hominini".to_string()), Some(0.1))
+                ),
+                (
+                    "1.1",
+                    NewickValue::new(Some("Homo sapiens".to_string()), Some(0.1))
+                ),
+                (
+                    "1.2",
+                    NewickValue::new(Some("Pan troglodytes".to_string()), Some(0.2))
+                ),
+                ("2", NewickValue::new(Some("O'Brien".to_string()), None)),
+                ("3", NewickValue::new(None, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_parse_newick_with_closures() {
+        let tree: Tree<String, f64> = Tree::parse_newick(
+            "(A:1,B:2)C;",
+            |_, x| x.name.clone().unwrap_or_default(),
+            |x| x.length.unwrap_or_default(),
+        )
+        .unwrap();
+        assert_eq!(tree.root().id(), "C");
+        assert_eq!(tree.root().query(&"B".to_string()).unwrap().value(), &2.0);
+        // siblings with the same id are reported rather than replaced
+        let parse = |text: &str| {
+            Tree::parse_newick(
+                text,
+                |_, x| x.name.clone().unwrap_or_default(),
+                |x| x.length.unwrap_or_default(),
+            )
+        };
+        assert_eq!(
+            parse("((x:1,y:2)A:1,(z:3)A:2)R;").unwrap_err(),
+            NewickError::DuplicateId { position: 14 }
+        );
+        assert_eq!(
+            parse("((x:1,y:2)A:1,(x:3)B:2)R;").unwrap().root().count(),
+            6
+        );
+        assert_eq!(
+            NewickError::DuplicateId { position: 14 }.to_string(),
+            "duplicate id at position 14"
+        );
+    }
+
+    #[test]
+    fn test_should_write_newick() {
+        let text = "((A:0.1,'B b':0.2)'C_1',,'D''s':3)root;";
+        let tree = Tree::from_newick(text).unwrap();
+        assert_eq!(tree.to_newick(), text);
+        let tree: Tree<&str, usize> = Tree::new(Node::new("a", 1).with_child(Node::new("b", 2)));
+        assert_eq!(
+            tree.to_newick_with(|x| NewickValue::new(
+                Some(x.id().to_string()),
+                Some(*x.value() as f64)
+            )),
+            "(b:2)a:1;"
+        );
+    }
+
+    #[test]
+    fn test_should_report_newick_errors() {
+        assert_eq!(
+            Tree::from_newick("(A,B)").unwrap_err(),
+            NewickError::UnexpectedEnd
+        );
+        assert_eq!(
+            Tree::from_newick("(A,'B);").unwrap_err(),
+            NewickError::UnexpectedEnd
+        );
+        assert_eq!(
+            Tree::from_newick("(A;B);").unwrap_err(),
+            NewickError::UnexpectedChar {
+                position: 2,
+                found: ';'
+            }
+        );
+        assert_eq!(
+            Tree::from_newick("(A:x,B);").unwrap_err(),
+            NewickError::InvalidLength { position: 3 }
+        );
+        assert_eq!(
+            Tree::from_newick("A;B").unwrap_err(),
+            NewickError::UnexpectedChar {
+                position: 2,
+                found: 'B'
+            }
+        );
+    }
+}
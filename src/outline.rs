@@ -0,0 +1,329 @@
+//! # Outline
+//!
+//! Parse and emit trees as indented-outline text
+
+use std::fmt;
+
+use crate::{Node, Tree};
+
+/// Bullet prefix which may precede the label of an outline line
+const BULLET: &str = "- ";
+/// Separator between the id and the value in the labels written by [`Tree::to_outline_with_ids`]
+const ID_SEPARATOR: &str = ": ";
+
+/// Describes an error which occurred while parsing an outline
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OutlineError {
+    /// The outline doesn't contain any node
+    Empty,
+    /// The line is not correctly indented
+    BadIndentation { line: usize },
+    /// The line declares a second node at root level
+    MultipleRoots { line: usize },
+    /// The line declares a node with the same id as one of its siblings
+    DuplicateId { line: usize },
+    /// The label of the line doesn't contain the id separator
+    MissingId { line: usize },
+}
+
+impl fmt::Display for OutlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "outline is empty"),
+            Self::BadIndentation { line } => write!(f, "bad indentation at line {line}"),
+            Self::MultipleRoots { line } => write!(f, "multiple roots at line {line}"),
+            Self::DuplicateId { line } => write!(f, "duplicate id at line {line}"),
+            Self::MissingId { line } => write!(f, "missing id at line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for OutlineError {}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Parse a [`Tree`] from an indented outline.
+    ///
+    /// Each non-blank line is a node; every level is indented by two spaces or by a tab
+    /// and the label may be preceded by a `- ` bullet.
+    /// `id_fn` and `value_fn` are called with the label of each line to build the node;
+    /// siblings must have different ids.
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let tree: Tree<String, String> = Tree::parse_outline(
+    ///     "- /\n  - bin\n    - ls\n  - tmp",
+    ///     |label| label.to_string(),
+    ///     |label| label.to_string(),
+    /// ).unwrap();
+    /// assert_eq!(tree.root().count(), 4);
+    /// assert_eq!(tree.root().route_by_node(&"ls".to_string()).unwrap(), vec![0, 0]);
+    /// ```
+    pub fn parse_outline<I, V>(
+        text: &str,
+        mut id_fn: I,
+        mut value_fn: V,
+    ) -> Result<Self, OutlineError>
+    where
+        I: FnMut(&str) -> U,
+        V: FnMut(&str) -> T,
+    {
+        parse_lines(text, |label, _| Ok((id_fn(label), value_fn(label))))
+    }
+
+    /// Parse a [`Tree`] from an indented outline written by [`Tree::to_outline_with_ids`].
+    ///
+    /// The label of each line is split at the first `: `; `id_fn` is called with the text before it
+    /// and `value_fn` with the text after it. Siblings must have different ids.
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let text = "- 0: /\n  - 1: bin\n  - 2: bin\n";
+    /// let tree: Tree<usize, String> = Tree::parse_outline_with_ids(
+    ///     text,
+    ///     |id| id.parse().unwrap(),
+    ///     |value| value.to_string(),
+    /// ).unwrap();
+    /// assert_eq!(tree.root().query(&2).unwrap().value(), "bin");
+    /// assert_eq!(tree.to_outline_with_ids(), text);
+    /// ```
+    pub fn parse_outline_with_ids<I, V>(
+        text: &str,
+        mut id_fn: I,
+        mut value_fn: V,
+    ) -> Result<Self, OutlineError>
+    where
+        I: FnMut(&str) -> U,
+        V: FnMut(&str) -> T,
+    {
+        parse_lines(text, |label, line| {
+            let (id, value) = label
+                .split_once(ID_SEPARATOR)
+                .ok_or(OutlineError::MissingId { line })?;
+            Ok((id_fn(id), value_fn(value)))
+        })
+    }
+}
+
+impl<U: PartialEq, T: fmt::Display> Tree<U, T> {
+    /// Emit the [`Tree`] as an indented outline, which can be parsed back with [`Tree::parse_outline`].
+    ///
+    /// Each node is written on its own line as a `- ` bullet followed by its value,
+    /// indented by two spaces per level. Ids are not written, so the outline is parsed back into the same tree
+    /// only if ids can be derived from the values and siblings have different values;
+    /// use [`Tree::to_outline_with_ids`] otherwise. Values containing line breaks can't be parsed back.
+    pub fn to_outline(&self) -> String {
+        write_lines(self.root(), &|x| x.value.to_string())
+    }
+}
+
+impl<U: PartialEq + fmt::Display, T: fmt::Display> Tree<U, T> {
+    /// Emit the [`Tree`] as an indented outline where each label is made of the id and the value of the node,
+    /// separated by `: `, which can be parsed back with [`Tree::parse_outline_with_ids`].
+    ///
+    /// Ids containing `: ` and ids or values containing line breaks can't be parsed back.
+    pub fn to_outline_with_ids(&self) -> String {
+        write_lines(self.root(), &|x| {
+            format!("{}{ID_SEPARATOR}{}", x.id, x.value)
+        })
+    }
+}
+
+/// Parse the lines of an outline, where `node_fn` returns the id and the value of the node
+/// from the label and the number of each line
+fn parse_lines<U, T, F>(text: &str, mut node_fn: F) -> Result<Tree<U, T>, OutlineError>
+where
+    U: PartialEq,
+    F: FnMut(&str, usize) -> Result<(U, T), OutlineError>,
+{
+    // stack of the nodes which are still open; its length is the level of the next child
+    let mut stack: Vec<Node<U, T>> = Vec::new();
+    let mut has_root = false;
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (level, label) =
+            split_indentation(line).ok_or(OutlineError::BadIndentation { line: line_no })?;
+        if level == 0 && has_root {
+            return Err(OutlineError::MultipleRoots { line: line_no });
+        }
+        if level > stack.len() {
+            return Err(OutlineError::BadIndentation { line: line_no });
+        }
+        // close nodes until the parent of this line is on top
+        close_nodes(&mut stack, level);
+        let label = label.strip_prefix(BULLET).unwrap_or(label);
+        let (id, value) = node_fn(label, line_no)?;
+        if let Some(parent) = stack.last() {
+            if parent.children.iter().any(|x| x.id == id) {
+                return Err(OutlineError::DuplicateId { line: line_no });
+            }
+        }
+        stack.push(Node::new(id, value));
+        has_root = true;
+    }
+    close_nodes(&mut stack, 1);
+    stack.pop().map(Tree::new).ok_or(OutlineError::Empty)
+}
+
+/// Write each node of the subtree of `node` on its own line, labelled by `label_fn`
+fn write_lines<U, T, F>(node: &Node<U, T>, label_fn: &F) -> String
+where
+    F: Fn(&Node<U, T>) -> String,
+{
+    /// Private recursive call for write_lines
+    fn write_lines_r<U, T, F>(node: &Node<U, T>, level: usize, label_fn: &F, outline: &mut String)
+    where
+        F: Fn(&Node<U, T>) -> String,
+    {
+        outline.push_str(&"  ".repeat(level));
+        outline.push_str(BULLET);
+        outline.push_str(&label_fn(node));
+        outline.push('\n');
+        node.children
+            .iter()
+            .for_each(|x| write_lines_r(x, level + 1, label_fn, outline));
+    }
+    let mut outline = String::new();
+    write_lines_r(node, 0, label_fn, &mut outline);
+    outline
+}
+
+/// Split the indentation from the line, returning the level and the rest of the line.
+/// Returns `None` if the indentation is not made of tabs and pairs of spaces
+fn split_indentation(line: &str) -> Option<(usize, &str)> {
+    let mut level = 0;
+    let mut rest = line;
+    loop {
+        if let Some(x) = rest.strip_prefix('\t') {
+            rest = x;
+        } else if let Some(x) = rest.strip_prefix("  ") {
+            rest = x;
+        } else if rest.starts_with(' ') {
+            return None;
+        } else {
+            return Some((level, rest));
+        }
+        level += 1;
+    }
+}
+
+/// Pop nodes from the stack, appending each one to the children of its parent, until the stack is `len` long
+fn close_nodes<U, T>(stack: &mut Vec<Node<U, T>>, len: usize) {
+    while stack.len() > len {
+        let node = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_should_parse_outline() {
+        let tree: Tree<String, String> = Tree::parse_outline(
+            "/\n  bin\n    ls\n\n    pwd\n\ttmp\n\t\t- dump.txt\n",
+            |x| format!("#{x}"),
+            |x| x.to_string(),
+        )
+        .unwrap();
+        assert_eq!(tree.root().id(), "#/");
+        assert_eq!(tree.root().count(), 6);
+        assert_eq!(
+            tree.root().node_by_route(&[1, 0]).unwrap().value(),
+            "dump.txt"
+        );
+        assert_eq!(
+            tree.root().route_by_node(&"#pwd".to_string()).unwrap(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_should_round_trip_outline() {
+        let text = "- /\n  - bin\n    - ls\n    -  pwd\n  - - tmp\n";
+        let tree: Tree<String, String> =
+            Tree::parse_outline(text, |x| x.to_string(), |x| x.to_string()).unwrap();
+        assert_eq!(tree.to_outline(), text);
+        let parsed: Tree<String, String> =
+            Tree::parse_outline(&tree.to_outline(), |x| x.to_string(), |x| x.to_string()).unwrap();
+        assert_eq!(parsed, tree);
+    }
+
+    #[test]
+    fn test_should_round_trip_outline_with_ids() {
+        // siblings with the same value, and ids not derived from values
+        let tree: Tree<usize, String> = Tree::new(
+            Node::new(0, String::from("/"))
+                .with_child(
+                    Node::new(1, String::from("tmp"))
+                        .with_child(Node::new(3, String::from("a: b"))),
+                )
+                .with_child(Node::new(2, String::from("tmp"))),
+        );
+        let text = tree.to_outline_with_ids();
+        assert_eq!(text, "- 0: /\n  - 1: tmp\n    - 3: a: b\n  - 2: tmp\n");
+        let parsed: Tree<usize, String> =
+            Tree::parse_outline_with_ids(&text, |x| x.parse().unwrap(), |x| x.to_string()).unwrap();
+        assert_eq!(parsed, tree);
+        // the outline without ids can't be parsed back into this tree
+        let parse = |text: &str| -> Result<Tree<String, String>, OutlineError> {
+            Tree::parse_outline(text, |x| x.to_string(), |x| x.to_string())
+        };
+        assert_eq!(
+            parse(&tree.to_outline()).unwrap_err(),
+            OutlineError::DuplicateId { line: 4 }
+        );
+        let error = Tree::<String, String>::parse_outline_with_ids(
+            "- 0: /\n  - tmp",
+            |x| x.to_string(),
+            |x| x.to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(error, OutlineError::MissingId { line: 2 });
+    }
+
+    #[test]
+    fn test_should_report_outline_errors() {
+        let parse = |text: &str| -> Result<Tree<String, String>, OutlineError> {
+            Tree::parse_outline(text, |x| x.to_string(), |x| x.to_string())
+        };
+        assert_eq!(parse("\n  \n").unwrap_err(), OutlineError::Empty);
+        assert_eq!(
+            parse("a\n   b").unwrap_err(),
+            OutlineError::BadIndentation { line: 2 }
+        );
+        assert_eq!(
+            parse("a\n  b\n      c").unwrap_err(),
+            OutlineError::BadIndentation { line: 3 }
+        );
+        assert_eq!(
+            parse("  a").unwrap_err(),
+            OutlineError::BadIndentation { line: 1 }
+        );
+        assert_eq!(
+            parse("a\n  b\nc").unwrap_err(),
+            OutlineError::MultipleRoots { line: 3 }
+        );
+        assert_eq!(
+            parse("r\n  a\n    x\n  a\n    y").unwrap_err(),
+            OutlineError::DuplicateId { line: 4 }
+        );
+        // the same id under different parents is not a sibling
+        assert_eq!(
+            parse("r\n  a\n    x\n  b\n    x").unwrap().root().count(),
+            5
+        );
+        assert_eq!(
+            OutlineError::MultipleRoots { line: 3 }.to_string(),
+            "multiple roots at line 3"
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! # Patch
+//!
+//! Apply edit scripts to trees atomically
+
+use std::fmt;
+use std::hash::Hash;
+
+use crate::{diff, DiffError, Edit, Node, Tree};
+
+/// A sequence of [`Edit`]s to apply to a [`Tree`] with [`Tree::apply`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patch<U, T> {
+    pub edits: Vec<Edit<U, T>>,
+}
+
+impl<U, T> Patch<U, T> {
+    /// Instantiates a new [`Patch`]
+    pub fn new(edits: Vec<Edit<U, T>>) -> Self {
+        Self { edits }
+    }
+
+    /// Returns whether the patch has no edits
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+}
+
+impl<U: Eq + Hash + Clone, T: PartialEq + Clone> Patch<U, T> {
+    /// Instantiates the [`Patch`] which turns `old` into `new`. See [`diff`]
+    pub fn diff(old: &Tree<U, T>, new: &Tree<U, T>) -> Result<Self, DiffError<U>> {
+        diff(old, new).map(Self::new)
+    }
+}
+
+impl<U, T> From<Vec<Edit<U, T>>> for Patch<U, T> {
+    fn from(edits: Vec<Edit<U, T>>) -> Self {
+        Self::new(edits)
+    }
+}
+
+/// Describes why an edit of a [`Patch`] couldn't be applied.
+/// `edit` is the index of the edit in the patch
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PatchError<U> {
+    /// The node doesn't exist
+    MissingNode { edit: usize, id: U },
+    /// The node to insert already exists
+    DuplicateId { edit: usize, id: U },
+    /// The node is not a child of the expected parent
+    NotAChild { edit: usize, id: U, parent: U },
+    /// The node would be moved into its own subtree
+    Cycle { edit: usize, id: U },
+    /// The order is not a permutation of the children of the node
+    InvalidOrder { edit: usize, parent: U },
+    /// The current value of the node is not the expected one
+    ValueMismatch { edit: usize, id: U },
+}
+
+impl<U: fmt::Debug> fmt::Display for PatchError<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNode { edit, id } => write!(f, "edit {edit}: node {id:?} doesn't exist"),
+            Self::DuplicateId { edit, id } => {
+                write!(f, "edit {edit}: node {id:?} already exists")
+            }
+            Self::NotAChild { edit, id, parent } => {
+                write!(f, "edit {edit}: node {id:?} is not a child of {parent:?}")
+            }
+            Self::Cycle { edit, id } => {
+                write!(
+                    f,
+                    "edit {edit}: node {id:?} can't be moved into its subtree"
+                )
+            }
+            Self::InvalidOrder { edit, parent } => {
+                write!(
+                    f,
+                    "edit {edit}: invalid order of the children of {parent:?}"
+                )
+            }
+            Self::ValueMismatch { edit, id } => {
+                write!(f, "edit {edit}: node {id:?} has an unexpected value")
+            }
+        }
+    }
+}
+
+impl<U: fmt::Debug> std::error::Error for PatchError<U> {}
+
+impl<U: PartialEq + Clone, T: PartialEq + Clone> Tree<U, T> {
+    /// Apply the edits of `patch` in sequence.
+    ///
+    /// Edits are applied by id; routes are ignored. Each edit is checked before being applied:
+    /// nodes must exist (or not exist, for inserts) under the expected parents and updated values must
+    /// match their old value. If any edit fails, the tree is left untouched and the error is returned.
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Patch, Tree};
+    ///
+    /// let old: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/a", 1)));
+    /// let new: Tree<&str, usize> = Tree::new(
+    ///     Node::new("/", 0).with_child(Node::new("/b", 2)).with_child(Node::new("/a", 3)),
+    /// );
+    /// let patch = Patch::diff(&old, &new).unwrap();
+    /// let mut tree = old.clone();
+    /// tree.apply(&patch).unwrap();
+    /// assert_eq!(tree, new);
+    /// // the patch can't be applied twice
+    /// assert!(tree.apply(&patch).is_err());
+    /// assert_eq!(tree, new);
+    /// ```
+    pub fn apply(&mut self, patch: &Patch<U, T>) -> Result<(), PatchError<U>> {
+        let mut root = self.root.clone();
+        for (i, edit) in patch.edits.iter().enumerate() {
+            apply_edit(&mut root, i, edit)?;
+        }
+        self.root = root;
+        Ok(())
+    }
+}
+
+/// Apply `edit`, whose index is `i`, to `root`
+fn apply_edit<U, T>(root: &mut Node<U, T>, i: usize, edit: &Edit<U, T>) -> Result<(), PatchError<U>>
+where
+    U: PartialEq + Clone,
+    T: PartialEq + Clone,
+{
+    let missing = |id: &U| PatchError::MissingNode {
+        edit: i,
+        id: id.clone(),
+    };
+    match edit {
+        Edit::Insert {
+            id,
+            parent,
+            index,
+            value,
+            ..
+        } => {
+            if root.query(id).is_some() {
+                return Err(PatchError::DuplicateId {
+                    edit: i,
+                    id: id.clone(),
+                });
+            }
+            let parent = root.query_mut(parent).ok_or_else(|| missing(parent))?;
+            let index = (*index).min(parent.children.len());
+            parent
+                .children
+                .insert(index, Node::new(id.clone(), value.clone()));
+        }
+        Edit::Remove { id, parent, .. } => {
+            take_child(root, i, id, parent)?;
+        }
+        Edit::Move {
+            id,
+            from_parent,
+            to_parent,
+            index,
+            ..
+        } => {
+            let node = root.query(id).ok_or_else(|| missing(id))?;
+            if node.query(to_parent).is_some() {
+                return Err(PatchError::Cycle {
+                    edit: i,
+                    id: id.clone(),
+                });
+            }
+            if root.query(to_parent).is_none() {
+                return Err(missing(to_parent));
+            }
+            let node = take_child(root, i, id, from_parent)?;
+            let parent = root.query_mut(to_parent).unwrap();
+            let index = (*index).min(parent.children.len());
+            parent.children.insert(index, node);
+        }
+        Edit::Reorder { parent, order, .. } => {
+            let node = root.query_mut(parent).ok_or_else(|| missing(parent))?;
+            let invalid = || PatchError::InvalidOrder {
+                edit: i,
+                parent: parent.clone(),
+            };
+            if order.len() != node.children.len() {
+                return Err(invalid());
+            }
+            let mut children: Vec<Option<Node<U, T>>> = node.children.drain(..).map(Some).collect();
+            let mut sorted = Vec::with_capacity(children.len());
+            for id in order.iter() {
+                match children
+                    .iter_mut()
+                    .find(|x| x.as_ref().is_some_and(|x| &x.id == id))
+                {
+                    Some(child) => sorted.push(child.take().unwrap()),
+                    None => return Err(invalid()),
+                }
+            }
+            node.children = sorted;
+        }
+        Edit::UpdateValue { id, old, new, .. } => {
+            let node = root.query_mut(id).ok_or_else(|| missing(id))?;
+            if &node.value != old {
+                return Err(PatchError::ValueMismatch {
+                    edit: i,
+                    id: id.clone(),
+                });
+            }
+            node.value = new.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Remove the child `id` from the children of `parent`, returning it
+fn take_child<U, T>(
+    root: &mut Node<U, T>,
+    i: usize,
+    id: &U,
+    parent: &U,
+) -> Result<Node<U, T>, PatchError<U>>
+where
+    U: PartialEq + Clone,
+{
+    if root.query(id).is_none() {
+        return Err(PatchError::MissingNode {
+            edit: i,
+            id: id.clone(),
+        });
+    }
+    let not_a_child = || PatchError::NotAChild {
+        edit: i,
+        id: id.clone(),
+        parent: parent.clone(),
+    };
+    let parent = root.query_mut(parent).ok_or_else(not_a_child)?;
+    let index = parent
+        .children
+        .iter()
+        .position(|x| &x.id == id)
+        .ok_or_else(not_a_child)?;
+    Ok(parent.children.remove(index))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_apply_diff() {
+        let old = tree();
+        let new: Tree<&'static str, usize> = Tree::new(
+            Node::new("/", 9)
+                .with_child(
+                    Node::new("/tmp", 4)
+                        .with_child(Node::new("/tmp/c", 7).with_child(Node::new("/tmp/c/d", 8))),
+                )
+                .with_child(
+                    Node::new("/bin", 10)
+                        .with_child(Node::new("/bin/pwd", 3))
+                        .with_child(Node::new("/tmp/a/b", 6))
+                        .with_child(Node::new("/bin/ls", 2)),
+                ),
+        );
+        let mut tree = old.clone();
+        tree.apply(&Patch::diff(&old, &new).unwrap()).unwrap();
+        assert_eq!(tree, new);
+        let mut tree = new.clone();
+        tree.apply(&Patch::diff(&new, &old).unwrap()).unwrap();
+        assert_eq!(tree, old);
+        tree.apply(&Patch::new(vec![])).unwrap();
+        assert_eq!(tree, old);
+    }
+
+    #[test]
+    fn test_should_apply_patches_atomically() {
+        let check = |edits: Vec<Edit<&'static str, usize>>, error: PatchError<&'static str>| {
+            let mut tree = tree();
+            let mut patch = Patch::new(vec![Edit::UpdateValue {
+                id: "/",
+                old: 0,
+                new: 1,
+                route: vec![],
+            }]);
+            patch.edits.extend(edits);
+            assert_eq!(tree.apply(&patch).unwrap_err(), error);
+            assert_eq!(tree, self::tree());
+        };
+        check(
+            vec![Edit::Insert {
+                id: "/bin/ls",
+                parent: "/tmp",
+                index: 0,
+                value: 0,
+                route: vec![],
+            }],
+            PatchError::DuplicateId {
+                edit: 1,
+                id: "/bin/ls",
+            },
+        );
+        check(
+            vec![Edit::Remove {
+                id: "/tmp/a/b",
+                parent: "/tmp",
+                route: vec![],
+            }],
+            PatchError::NotAChild {
+                edit: 1,
+                id: "/tmp/a/b",
+                parent: "/tmp",
+            },
+        );
+        check(
+            vec![Edit::Move {
+                id: "/tmp",
+                from_parent: "/",
+                to_parent: "/tmp/a",
+                index: 0,
+                route: vec![],
+            }],
+            PatchError::Cycle {
+                edit: 1,
+                id: "/tmp",
+            },
+        );
+        check(
+            vec![Edit::Reorder {
+                parent: "/bin",
+                order: vec!["/bin/ls", "/bin/ls"],
+                route: vec![],
+            }],
+            PatchError::InvalidOrder {
+                edit: 1,
+                parent: "/bin",
+            },
+        );
+        check(
+            vec![Edit::UpdateValue {
+                id: "/var",
+                old: 0,
+                new: 1,
+                route: vec![],
+            }],
+            PatchError::MissingNode {
+                edit: 1,
+                id: "/var",
+            },
+        );
+        check(
+            vec![Edit::UpdateValue {
+                id: "/tmp",
+                old: 0,
+                new: 1,
+                route: vec![],
+            }],
+            PatchError::ValueMismatch {
+                edit: 1,
+                id: "/tmp",
+            },
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_should_serialize_patch() {
+        let old: Tree<String, usize> = Tree::new(Node::new("/".to_string(), 0));
+        let new: Tree<String, usize> =
+            Tree::new(Node::new("/".to_string(), 1).with_child(Node::new("/a".to_string(), 2)));
+        let patch = Patch::diff(&old, &new).unwrap();
+        let json = serde_json::to_string(&patch).unwrap();
+        let patch: Patch<String, usize> = serde_json::from_str(&json).unwrap();
+        let mut tree = old.clone();
+        tree.apply(&patch).unwrap();
+        assert_eq!(tree, new);
+    }
+}
@@ -0,0 +1,230 @@
+//! # Paths
+//!
+//! Import trees from materialized paths (e.g. `Root/Sales/EMEA`) and export them as CSV
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{dotted_route, Node, Tree};
+
+/// Describes how node paths are written by [`Tree::to_csv`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathStyle {
+    /// The path is the node id, which is the materialized path for trees built with [`Tree::from_paths`]
+    Materialized,
+    /// The path is the dotted position of the node (e.g. `1.3.2`, where `1` is the first child of the root),
+    /// as for the ids of [`Tree::from_markdown`] and [`Tree::from_newick`]; the path of the root is empty
+    Positional,
+}
+
+/// Describes an error which occurred while building a tree from paths.
+/// `row` is the index of the row in the input, starting from `0`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathsError {
+    /// There are no rows
+    Empty,
+    /// The path is empty or contains an empty segment
+    InvalidPath { row: usize },
+    /// The path doesn't start with the root of the previous rows
+    MultipleRoots { row: usize },
+    /// The path has already been defined by a previous row
+    Conflict { row: usize, path: String },
+}
+
+impl fmt::Display for PathsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no rows"),
+            Self::InvalidPath { row } => write!(f, "invalid path at row {row}"),
+            Self::MultipleRoots { row } => write!(f, "multiple roots at row {row}"),
+            Self::Conflict { row, path } => {
+                write!(f, "path {path} at row {row} has already been defined")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathsError {}
+
+impl<T: Default> Tree<String, T> {
+    /// Build a [`Tree`] from rows made of a materialized path (e.g. `Root/Sales/EMEA`) and a value.
+    ///
+    /// Each node has its path as id; intermediate nodes which are not described by any row
+    /// are created with the default value. All the paths must start from the same root.
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let tree: Tree<String, usize> = Tree::from_paths(
+    ///     vec![("Root/Sales/EMEA", 12), ("Root/Sales/APAC", 7), ("Root", 1)],
+    ///     "/",
+    /// ).unwrap();
+    /// assert_eq!(tree.root().value(), &1);
+    /// assert_eq!(tree.root().query(&"Root/Sales".to_string()).unwrap().value(), &0);
+    /// assert_eq!(tree.root().route_by_node(&"Root/Sales/APAC".to_string()).unwrap(), vec![0, 1]);
+    /// ```
+    pub fn from_paths<I, S>(rows: I, separator: &str) -> Result<Self, PathsError>
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: AsRef<str>,
+    {
+        let mut tree: Option<Self> = None;
+        let mut defined: HashSet<String> = HashSet::new();
+        for (row, (path, value)) in rows.into_iter().enumerate() {
+            let segments: Vec<&str> = path.as_ref().split(separator).collect();
+            if segments.iter().any(|x| x.is_empty()) {
+                return Err(PathsError::InvalidPath { row });
+            }
+            let tree = tree
+                .get_or_insert_with(|| Tree::new(Node::new(segments[0].to_string(), T::default())));
+            if tree.root().id() != segments[0] {
+                return Err(PathsError::MultipleRoots { row });
+            }
+            // walk the path, creating the missing nodes
+            let ids = (1..segments.len()).map(|i| segments[..=i].join(separator));
+            let node = tree.insert_path(ids, value, |_| T::default());
+            if !defined.insert(node.id.clone()) {
+                return Err(PathsError::Conflict {
+                    row,
+                    path: node.id.clone(),
+                });
+            }
+        }
+        tree.ok_or(PathsError::Empty)
+    }
+}
+
+impl<U: PartialEq + fmt::Display, T: fmt::Display> Tree<U, T> {
+    /// Export the [`Tree`] as CSV with a `path,value` header and a row for each node in pre-order
+    ///
+    /// ```rust
+    /// use orange_trees::{PathStyle, Tree};
+    ///
+    /// let tree: Tree<String, usize> =
+    ///     Tree::from_paths(vec![("Root/Sales", 12), ("Root/HR", 7)], "/").unwrap();
+    /// assert_eq!(
+    ///     tree.to_csv(PathStyle::Materialized),
+    ///     "path,value\nRoot,0\nRoot/Sales,12\nRoot/HR,7\n"
+    /// );
+    /// assert_eq!(
+    ///     tree.to_csv(PathStyle::Positional),
+    ///     "path,value\n,0\n1,12\n2,7\n"
+    /// );
+    /// ```
+    pub fn to_csv(&self, style: PathStyle) -> String {
+        /// Private recursive call for to_csv
+        fn to_csv_r<U: fmt::Display, T: fmt::Display>(
+            node: &Node<U, T>,
+            style: PathStyle,
+            route: &mut Vec<usize>,
+            csv: &mut String,
+        ) {
+            let path = match style {
+                PathStyle::Materialized => node.id.to_string(),
+                PathStyle::Positional => dotted_route(route),
+            };
+            csv.push_str(&escape(&path));
+            csv.push(',');
+            csv.push_str(&escape(&node.value.to_string()));
+            csv.push('\n');
+            for (i, child) in node.children.iter().enumerate() {
+                route.push(i);
+                to_csv_r(child, style, route, csv);
+                route.pop();
+            }
+        }
+        let mut csv = String::from("path,value\n");
+        to_csv_r(self.root(), style, &mut Vec::new(), &mut csv);
+        csv
+    }
+}
+
+/// Quote a CSV field if required
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_should_build_tree_from_paths() {
+        let tree: Tree<String, &str> = Tree::from_paths(
+            vec![
+                ("Root::Sales::EMEA", "Anna"),
+                ("Root::Sales", "Bruno"),
+                ("Root::Sales::EMEA::Italy", "Carla"),
+                ("Root::R&D", "Dario"),
+            ],
+            "::",
+        )
+        .unwrap();
+        let nodes: Vec<(&str, &str)> = tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| (x.id().as_str(), *x.value()))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                ("Root", ""),
+                ("Root::Sales", "Bruno"),
+                ("Root::Sales::EMEA", "Anna"),
+                ("Root::Sales::EMEA::Italy", "Carla"),
+                ("Root::R&D", "Dario"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_report_conflicting_rows() {
+        let build = |rows: Vec<(&str, usize)>| Tree::from_paths(rows, "/");
+        assert_eq!(build(vec![]).unwrap_err(), PathsError::Empty);
+        assert_eq!(
+            build(vec![("a/b", 1), ("a//c", 2)]).unwrap_err(),
+            PathsError::InvalidPath { row: 1 }
+        );
+        assert_eq!(
+            build(vec![("", 1)]).unwrap_err(),
+            PathsError::InvalidPath { row: 0 }
+        );
+        assert_eq!(
+            build(vec![("a/b", 1), ("c/b", 2)]).unwrap_err(),
+            PathsError::MultipleRoots { row: 1 }
+        );
+        assert_eq!(
+            build(vec![("a/b", 1), ("a/b/c", 2), ("a/b", 3)]).unwrap_err(),
+            PathsError::Conflict {
+                row: 2,
+                path: "a/b".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_should_export_csv() {
+        let tree: Tree<String, String> = Tree::new(
+            Node::new("Root".to_string(), "Acme, Inc.".to_string()).with_child(
+                Node::new("Root/Sales".to_string(), "say \"hi\"".to_string())
+                    .with_child(Node::new("Root/Sales/EMEA".to_string(), "x".to_string())),
+            ),
+        );
+        assert_eq!(
+            tree.to_csv(PathStyle::Materialized),
+            "path,value\nRoot,\"Acme, Inc.\"\nRoot/Sales,\"say \"\"hi\"\"\"\nRoot/Sales/EMEA,x\n"
+        );
+        assert_eq!(
+            tree.to_csv(PathStyle::Positional),
+            "path,value\n,\"Acme, Inc.\"\n1,\"say \"\"hi\"\"\"\n1.1,x\n"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! # Pattern
+//!
+//! Search occurrences of pattern trees, with wildcards, inside trees
+
+use crate::{Node, Tree};
+
+/// Describes which nodes are matched by a [`Pattern`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PatternKind<P> {
+    /// A node for which the matcher returns `true` on `P`
+    Node(P),
+    /// Any node
+    AnyNode,
+    /// Any number, including zero, of consecutive siblings with their subtrees. As root of a pattern, any node
+    AnySubtrees,
+}
+
+/// A pattern tree to search with [`Node::find_pattern`].
+///
+/// A pattern without children matches nodes with any children; otherwise each child of the node must be matched
+/// by a child of the pattern, and vice versa, except for [`PatternKind::AnySubtrees`] children, which
+/// absorb the children left unmatched
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pattern<P> {
+    kind: PatternKind<P>,
+    children: Vec<Pattern<P>>,
+    ordered: bool,
+}
+
+impl<P> Pattern<P> {
+    /// Instantiates a new [`Pattern`] matching nodes for which the matcher returns `true` on `p`
+    pub fn node(p: P) -> Self {
+        Self::new(PatternKind::Node(p))
+    }
+
+    /// Instantiates a new [`Pattern`] matching any node
+    pub fn any_node() -> Self {
+        Self::new(PatternKind::AnyNode)
+    }
+
+    /// Instantiates a new [`Pattern`] matching any number of sibling subtrees
+    pub fn any_subtrees() -> Self {
+        Self::new(PatternKind::AnySubtrees)
+    }
+
+    fn new(kind: PatternKind<P>) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+            ordered: true,
+        }
+    }
+
+    /// Add `child` to the children of the pattern
+    pub fn with_child(mut self, child: Pattern<P>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Match the children of the pattern regardless of the order of the children of the node
+    pub fn unordered(mut self) -> Self {
+        self.ordered = false;
+        self
+    }
+
+    /// Get reference to the kind of the pattern
+    pub fn kind(&self) -> &PatternKind<P> {
+        &self.kind
+    }
+
+    /// Get reference to the children of the pattern
+    pub fn children(&self) -> &[Pattern<P>] {
+        &self.children
+    }
+
+    /// Returns whether the children of the pattern must match the children of the node in order
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+
+    /// Returns whether the pattern matches the subtree of `node`
+    fn matches<U, T, F>(&self, node: &Node<U, T>, matcher: &F) -> bool
+    where
+        F: Fn(&P, &Node<U, T>) -> bool,
+    {
+        match &self.kind {
+            PatternKind::AnySubtrees => return true,
+            PatternKind::AnyNode => {}
+            PatternKind::Node(p) => {
+                if !matcher(p, node) {
+                    return false;
+                }
+            }
+        }
+        if self.children.is_empty() {
+            return true;
+        }
+        // whether each child of the pattern matches each child of the node
+        let matches: Vec<Vec<bool>> = self
+            .children
+            .iter()
+            .map(|x| {
+                node.children
+                    .iter()
+                    .map(|y| x.matches(y, matcher))
+                    .collect()
+            })
+            .collect();
+        match self.ordered {
+            true => self.matches_ordered(&matches, node.children.len()),
+            false => self.matches_unordered(&matches, node.children.len()),
+        }
+    }
+
+    /// Returns whether the children of the pattern match, in order, all the `len` children of the node
+    fn matches_ordered(&self, matches: &[Vec<bool>], len: usize) -> bool {
+        // reached[i][j]: the first `i` children of the pattern match the first `j` children of the node
+        let mut reached = vec![vec![false; len + 1]; self.children.len() + 1];
+        reached[0][0] = true;
+        for (i, child) in self.children.iter().enumerate() {
+            for j in 0..=len {
+                if !reached[i][j] {
+                    continue;
+                }
+                if matches!(child.kind, PatternKind::AnySubtrees) {
+                    reached[i + 1][j..].iter_mut().for_each(|x| *x = true);
+                    break;
+                } else if j < len && matches[i][j] {
+                    reached[i + 1][j + 1] = true;
+                }
+            }
+        }
+        reached[self.children.len()][len]
+    }
+
+    /// Returns whether the children of the pattern match all the `len` children of the node, in any order,
+    /// finding a maximum bipartite matching between them
+    fn matches_unordered(&self, matches: &[Vec<bool>], len: usize) -> bool {
+        /// Private recursive call for matches_unordered, looking for an augmenting path from `row`
+        fn augment(
+            row: usize,
+            matches: &[Vec<bool>],
+            visited: &mut [bool],
+            assigned: &mut [Option<usize>],
+        ) -> bool {
+            for j in 0..visited.len() {
+                if matches[row][j] && !visited[j] {
+                    visited[j] = true;
+                    if assigned[j].is_none_or(|x| augment(x, matches, visited, assigned)) {
+                        assigned[j] = Some(row);
+                        return true;
+                    }
+                }
+            }
+            false
+        }
+        let rows: Vec<usize> = (0..self.children.len())
+            .filter(|x| !matches!(self.children[*x].kind, PatternKind::AnySubtrees))
+            .collect();
+        let wildcard = rows.len() < self.children.len();
+        if rows.len() > len || (!wildcard && rows.len() != len) {
+            return false;
+        }
+        let mut assigned = vec![None; len];
+        rows.into_iter().all(|row| {
+            let mut visited = vec![false; len];
+            augment(row, matches, &mut visited, &mut assigned)
+        })
+    }
+}
+
+impl<U: PartialEq, T> Node<U, T> {
+    /// Find the nodes, in this branch, where `pattern` matches, returning them in pre-order.
+    /// `matcher` tells whether a [`PatternKind::Node`] matches a node, without looking at its children.
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Pattern};
+    ///
+    /// let node: Node<&str, &str> = Node::new("/", "dir")
+    ///     .with_child(
+    ///         Node::new("/bin", "dir")
+    ///             .with_child(Node::new("/bin/ls", "file"))
+    ///             .with_child(Node::new("/bin/pwd", "file")),
+    ///     )
+    ///     .with_child(Node::new("/tmp", "dir").with_child(Node::new("/tmp/a", "dir")));
+    /// // directories containing a file, and possibly other nodes
+    /// let pattern = Pattern::node("dir")
+    ///     .with_child(Pattern::node("file"))
+    ///     .with_child(Pattern::any_subtrees())
+    ///     .unordered();
+    /// let matches = node.find_pattern(&pattern, &|p, x| x.value() == p);
+    /// assert_eq!(matches.into_iter().map(|x| *x.id()).collect::<Vec<_>>(), vec!["/bin"]);
+    /// ```
+    pub fn find_pattern<P, F>(&self, pattern: &Pattern<P>, matcher: &F) -> Vec<&Self>
+    where
+        F: Fn(&P, &Self) -> bool,
+    {
+        self.find(&|x| pattern.matches(x, matcher))
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Find the nodes of the [`Tree`] where `pattern` matches. See [`Node::find_pattern`]
+    pub fn find_pattern<P, F>(&self, pattern: &Pattern<P>, matcher: &F) -> Vec<&Node<U, T>>
+    where
+        F: Fn(&P, &Node<U, T>) -> bool,
+    {
+        self.root.find_pattern(pattern, matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// An expression tree, where ids are unique and values are the labels
+    fn tree() -> Tree<usize, &'static str> {
+        // (a + b) * (b + a + 1)
+        Tree::new(
+            Node::new(0, "*")
+                .with_child(
+                    Node::new(1, "+")
+                        .with_child(Node::new(2, "a"))
+                        .with_child(Node::new(3, "b")),
+                )
+                .with_child(
+                    Node::new(4, "+")
+                        .with_child(Node::new(5, "b"))
+                        .with_child(Node::new(6, "a"))
+                        .with_child(Node::new(7, "1")),
+                ),
+        )
+    }
+
+    fn ids(nodes: Vec<&Node<usize, &'static str>>) -> Vec<usize> {
+        nodes.into_iter().map(|x| *x.id()).collect()
+    }
+
+    fn find(pattern: &Pattern<&'static str>) -> Vec<usize> {
+        ids(tree().find_pattern(pattern, &|p, x| x.value() == p))
+    }
+
+    #[test]
+    fn test_should_find_ordered_patterns() {
+        // single node patterns behave like find
+        assert_eq!(find(&Pattern::node("a")), vec![2, 6]);
+        assert_eq!(find(&Pattern::node("+")), vec![1, 4]);
+        assert_eq!(find(&Pattern::any_node()).len(), tree().root().count());
+        assert_eq!(find(&Pattern::any_subtrees()).len(), tree().root().count());
+        // children must match all the children
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::any_node());
+        assert_eq!(find(&pattern), vec![1]);
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::any_subtrees())
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::any_subtrees());
+        assert_eq!(find(&pattern), vec![1, 4]);
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("b"))
+            .with_child(Pattern::any_subtrees());
+        assert_eq!(find(&pattern), vec![4]);
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::node("b"))
+            .with_child(Pattern::any_subtrees())
+            .with_child(Pattern::node("1"));
+        assert_eq!(find(&pattern), Vec::<usize>::new());
+        // nested patterns
+        let pattern = Pattern::any_node()
+            .with_child(Pattern::any_subtrees())
+            .with_child(
+                Pattern::node("+")
+                    .with_child(Pattern::node("b"))
+                    .with_child(Pattern::any_subtrees()),
+            );
+        assert_eq!(find(&pattern), vec![0]);
+    }
+
+    #[test]
+    fn test_should_find_unordered_patterns() {
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("b"))
+            .with_child(Pattern::node("a"))
+            .unordered();
+        assert_eq!(find(&pattern), vec![1]);
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("b"))
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::any_subtrees())
+            .unordered();
+        assert_eq!(find(&pattern), vec![1, 4]);
+        // each child of the node is matched at most once
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::node("a"))
+            .with_child(Pattern::any_subtrees())
+            .unordered();
+        assert_eq!(find(&pattern), Vec::<usize>::new());
+        // requires reassigning a matched child
+        let pattern = Pattern::node("+")
+            .with_child(Pattern::any_node())
+            .with_child(Pattern::node("1"))
+            .with_child(Pattern::node("b"))
+            .unordered();
+        assert_eq!(find(&pattern), vec![4]);
+        // unordered children of ordered patterns
+        let pattern = Pattern::node("*")
+            .with_child(Pattern::any_node())
+            .with_child(
+                Pattern::node("+")
+                    .with_child(Pattern::node("1"))
+                    .with_child(Pattern::any_subtrees())
+                    .unordered(),
+            );
+        assert_eq!(find(&pattern), vec![0]);
+    }
+}
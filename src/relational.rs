@@ -0,0 +1,447 @@
+//! # Relational
+//!
+//! Export trees as closure-table and nested-set rows and rebuild them from those representations
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::{Node, Tree};
+
+/// A row of a closure table, which relates a node to each of its ancestors (and to itself)
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ClosureRow<U> {
+    pub ancestor: U,
+    pub descendant: U,
+    /// Distance between the ancestor and the descendant; `0` relates a node to itself
+    pub depth: usize,
+}
+
+impl<U> ClosureRow<U> {
+    /// Instantiates a new [`ClosureRow`]
+    pub fn new(ancestor: U, descendant: U, depth: usize) -> Self {
+        Self {
+            ancestor,
+            descendant,
+            depth,
+        }
+    }
+}
+
+/// A row of a nested set, where each node contains the intervals of its descendants
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct NestedSetRow<U> {
+    pub id: U,
+    pub lft: usize,
+    pub rgt: usize,
+    /// Depth of the node, where the root is `0`
+    pub depth: usize,
+}
+
+impl<U> NestedSetRow<U> {
+    /// Instantiates a new [`NestedSetRow`]
+    pub fn new(id: U, lft: usize, rgt: usize, depth: usize) -> Self {
+        Self {
+            id,
+            lft,
+            rgt,
+            depth,
+        }
+    }
+}
+
+/// Describes an error which occurred while rebuilding a tree from rows
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RelationalError<U> {
+    /// There are no rows
+    Empty,
+    /// There is no node without a parent
+    NoRoot,
+    /// There is more than one node without a parent
+    MultipleRoots,
+    /// The node is described by more than one row
+    DuplicateId(U),
+    /// The node has more than one parent
+    MultipleParents(U),
+    /// The node can't be reached from the root
+    Unreachable(U),
+    /// The closure rows of the node don't match its ancestry
+    Inconsistent(U),
+    /// The interval of the node is empty
+    InvalidInterval(U),
+    /// The intervals of the two nodes partially overlap
+    OverlappingIntervals(U, U),
+    /// The depth of the node doesn't match its intervals
+    InvalidDepth(U),
+}
+
+impl<U: fmt::Debug> fmt::Display for RelationalError<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no rows"),
+            Self::NoRoot => write!(f, "no root"),
+            Self::MultipleRoots => write!(f, "multiple roots"),
+            Self::DuplicateId(id) => write!(f, "duplicate node {id:?}"),
+            Self::MultipleParents(id) => write!(f, "node {id:?} has multiple parents"),
+            Self::Unreachable(id) => write!(f, "node {id:?} can't be reached from the root"),
+            Self::Inconsistent(id) => write!(f, "closure rows of node {id:?} are inconsistent"),
+            Self::InvalidInterval(id) => write!(f, "node {id:?} has an invalid interval"),
+            Self::OverlappingIntervals(a, b) => {
+                write!(f, "intervals of nodes {a:?} and {b:?} overlap")
+            }
+            Self::InvalidDepth(id) => write!(f, "node {id:?} has an invalid depth"),
+        }
+    }
+}
+
+impl<U: fmt::Debug> std::error::Error for RelationalError<U> {}
+
+impl<U: PartialEq + Clone, T> Tree<U, T> {
+    /// Export the [`Tree`] as closure-table rows, relating each node, in pre-order,
+    /// to all its ancestors from the root and to itself
+    ///
+    /// ```rust
+    /// use orange_trees::{ClosureRow, Node, Tree};
+    ///
+    /// let tree: Tree<&str, ()> = Tree::new(Node::new("a", ()).with_child(Node::new("b", ())));
+    /// assert_eq!(
+    ///     tree.closure_table(),
+    ///     vec![
+    ///         ClosureRow::new("a", "a", 0),
+    ///         ClosureRow::new("a", "b", 1),
+    ///         ClosureRow::new("b", "b", 0),
+    ///     ]
+    /// );
+    /// ```
+    pub fn closure_table(&self) -> Vec<ClosureRow<U>> {
+        /// Private recursive call for closure_table
+        fn closure_table_r<'a, U: Clone, T>(
+            node: &'a Node<U, T>,
+            ancestors: &mut Vec<&'a U>,
+            rows: &mut Vec<ClosureRow<U>>,
+        ) {
+            ancestors.push(&node.id);
+            rows.extend(ancestors.iter().enumerate().map(|(i, x)| {
+                ClosureRow::new((*x).clone(), node.id.clone(), ancestors.len() - 1 - i)
+            }));
+            node.children
+                .iter()
+                .for_each(|x| closure_table_r(x, ancestors, rows));
+            ancestors.pop();
+        }
+        let mut rows = Vec::new();
+        closure_table_r(self.root(), &mut Vec::new(), &mut rows);
+        rows
+    }
+
+    /// Export the [`Tree`] as nested-set rows in pre-order, numbering intervals from `1`
+    ///
+    /// ```rust
+    /// use orange_trees::{NestedSetRow, Node, Tree};
+    ///
+    /// let tree: Tree<&str, ()> = Tree::new(
+    ///     Node::new("a", ()).with_child(Node::new("b", ())).with_child(Node::new("c", ())),
+    /// );
+    /// assert_eq!(
+    ///     tree.nested_set(),
+    ///     vec![
+    ///         NestedSetRow::new("a", 1, 6, 0),
+    ///         NestedSetRow::new("b", 2, 3, 1),
+    ///         NestedSetRow::new("c", 4, 5, 1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn nested_set(&self) -> Vec<NestedSetRow<U>> {
+        /// Private recursive call for nested_set
+        fn nested_set_r<U: Clone, T>(
+            node: &Node<U, T>,
+            depth: usize,
+            counter: &mut usize,
+            rows: &mut Vec<NestedSetRow<U>>,
+        ) {
+            let index = rows.len();
+            rows.push(NestedSetRow::new(node.id.clone(), *counter, 0, depth));
+            *counter += 1;
+            node.children
+                .iter()
+                .for_each(|x| nested_set_r(x, depth + 1, counter, rows));
+            rows[index].rgt = *counter;
+            *counter += 1;
+        }
+        let mut rows = Vec::new();
+        nested_set_r(self.root(), 0, &mut 1, &mut rows);
+        rows
+    }
+}
+
+impl<U: Eq + Hash + Clone, T> Tree<U, T> {
+    /// Rebuild a [`Tree`] from closure-table rows, getting the value of each node from `value_fn`.
+    ///
+    /// Children are sorted by the order of the rows relating them to their parent.
+    /// Rows must describe exactly the ancestry of every node, including the relation of each node with itself.
+    pub fn from_closure_table<I, V>(rows: I, mut value_fn: V) -> Result<Self, RelationalError<U>>
+    where
+        I: IntoIterator<Item = ClosureRow<U>>,
+        V: FnMut(&U) -> T,
+    {
+        let rows: Vec<ClosureRow<U>> = rows.into_iter().collect();
+        // collect nodes and relations with parents, in order of appearance
+        let mut nodes: Vec<&U> = Vec::new();
+        let mut seen: HashSet<&U> = HashSet::new();
+        let mut parents: HashMap<&U, &U> = HashMap::new();
+        let mut children: HashMap<&U, Vec<&U>> = HashMap::new();
+        for row in rows.iter() {
+            for id in [&row.ancestor, &row.descendant] {
+                if seen.insert(id) {
+                    nodes.push(id);
+                }
+            }
+            if row.depth == 1 {
+                match parents.insert(&row.descendant, &row.ancestor) {
+                    Some(parent) if parent == &row.ancestor => continue,
+                    Some(_) => {
+                        return Err(RelationalError::MultipleParents(row.descendant.clone()))
+                    }
+                    None => {}
+                }
+                children
+                    .entry(&row.ancestor)
+                    .or_default()
+                    .push(&row.descendant);
+            }
+        }
+        let root = find_root(nodes.iter().copied(), &parents)?;
+        // compute the expected closure walking from the root
+        let mut expected: HashSet<(&U, &U, usize)> = HashSet::with_capacity(rows.len());
+        let mut reached: HashSet<&U> = HashSet::with_capacity(nodes.len());
+        let mut stack: Vec<(&U, Vec<&U>)> = vec![(root, vec![root])];
+        while let Some((id, ancestors)) = stack.pop() {
+            reached.insert(id);
+            expected.extend(
+                ancestors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| (*x, id, ancestors.len() - 1 - i)),
+            );
+            for child in children.get(id).into_iter().flatten() {
+                let mut ancestors = ancestors.clone();
+                ancestors.push(child);
+                stack.push((child, ancestors));
+            }
+        }
+        if let Some(id) = nodes.iter().find(|x| !reached.contains(*x)) {
+            return Err(RelationalError::Unreachable((*id).clone()));
+        }
+        let actual: HashSet<(&U, &U, usize)> = rows
+            .iter()
+            .map(|x| (&x.ancestor, &x.descendant, x.depth))
+            .collect();
+        if let Some((_, id, _)) = rows
+            .iter()
+            .map(|x| (&x.ancestor, &x.descendant, x.depth))
+            .find(|x| !expected.contains(x))
+            .or_else(|| expected.iter().copied().find(|x| !actual.contains(x)))
+        {
+            return Err(RelationalError::Inconsistent(id.clone()));
+        }
+        Ok(Tree::new(build_node(root, &children, &mut value_fn)))
+    }
+
+    /// Rebuild a [`Tree`] from nested-set rows, getting the value of each node from `value_fn`.
+    ///
+    /// Intervals must be properly nested, which means that they either contain each other or don't overlap.
+    pub fn from_nested_set<I, V>(rows: I, mut value_fn: V) -> Result<Self, RelationalError<U>>
+    where
+        I: IntoIterator<Item = NestedSetRow<U>>,
+        V: FnMut(&U) -> T,
+    {
+        let mut rows: Vec<NestedSetRow<U>> = rows.into_iter().collect();
+        rows.sort_by_key(|x| x.lft);
+        let mut nodes: HashSet<&U> = HashSet::new();
+        let mut parents: HashMap<&U, &U> = HashMap::new();
+        let mut children: HashMap<&U, Vec<&U>> = HashMap::new();
+        // open intervals containing the current one
+        let mut stack: Vec<&NestedSetRow<U>> = Vec::new();
+        for row in rows.iter() {
+            if row.lft >= row.rgt {
+                return Err(RelationalError::InvalidInterval(row.id.clone()));
+            }
+            if !nodes.insert(&row.id) {
+                return Err(RelationalError::DuplicateId(row.id.clone()));
+            }
+            while stack.last().map(|x| x.rgt < row.lft).unwrap_or(false) {
+                stack.pop();
+            }
+            if let Some(parent) = stack.last() {
+                if row.lft == parent.lft || row.rgt >= parent.rgt {
+                    return Err(RelationalError::OverlappingIntervals(
+                        parent.id.clone(),
+                        row.id.clone(),
+                    ));
+                }
+                parents.insert(&row.id, &parent.id);
+                children.entry(&parent.id).or_default().push(&row.id);
+            }
+            if row.depth != stack.len() {
+                return Err(RelationalError::InvalidDepth(row.id.clone()));
+            }
+            stack.push(row);
+        }
+        let root = find_root(rows.iter().map(|x| &x.id), &parents)?;
+        Ok(Tree::new(build_node(root, &children, &mut value_fn)))
+    }
+}
+
+/// Find the only node without a parent
+fn find_root<'a, U: Eq + Hash>(
+    mut nodes: impl Iterator<Item = &'a U>,
+    parents: &HashMap<&'a U, &'a U>,
+) -> Result<&'a U, RelationalError<U>> {
+    let mut roots = nodes.by_ref().filter(|x| !parents.contains_key(x));
+    match (roots.next(), roots.next()) {
+        (Some(root), None) => Ok(root),
+        (Some(_), Some(_)) => Err(RelationalError::MultipleRoots),
+        (None, _) if parents.is_empty() => Err(RelationalError::Empty),
+        (None, _) => Err(RelationalError::NoRoot),
+    }
+}
+
+/// Build the node `id` with its descendants
+fn build_node<U, T, V>(id: &U, children: &HashMap<&U, Vec<&U>>, value_fn: &mut V) -> Node<U, T>
+where
+    U: Eq + Hash + Clone,
+    V: FnMut(&U) -> T,
+{
+    let mut node = Node::new(id.clone(), value_fn(id));
+    for child in children.get(id).into_iter().flatten() {
+        node.add_child(build_node(child, children, value_fn));
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::fixtures::tree;
+
+    #[test]
+    fn test_should_round_trip_closure_table() {
+        let tree = tree();
+        let rows = tree.closure_table();
+        assert_eq!(rows.len(), 7 + 6 + 4 + 1);
+        assert!(rows.contains(&ClosureRow::new("/", "/bin/pwd", 2)));
+        let value = |x: &&str| *tree.root().query(x).unwrap().value();
+        assert_eq!(Tree::from_closure_table(rows, value).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_should_validate_closure_table() {
+        let build = |rows: Vec<ClosureRow<&'static str>>| Tree::from_closure_table(rows, |_| ());
+        assert_eq!(build(vec![]).unwrap_err(), RelationalError::Empty);
+        let mut rows = tree().closure_table();
+        rows.retain(|x| *x != ClosureRow::new("/", "/bin/ls", 2));
+        assert_eq!(
+            build(rows).unwrap_err(),
+            RelationalError::Inconsistent("/bin/ls")
+        );
+        let mut rows = tree().closure_table();
+        rows.push(ClosureRow::new("/tmp", "/bin/ls", 1));
+        assert_eq!(
+            build(rows).unwrap_err(),
+            RelationalError::MultipleParents("/bin/ls")
+        );
+        let mut rows = tree().closure_table();
+        rows.push(ClosureRow::new("/var", "/var", 0));
+        assert_eq!(build(rows).unwrap_err(), RelationalError::MultipleRoots);
+        // cycle
+        let rows = vec![
+            ClosureRow::new("a", "a", 0),
+            ClosureRow::new("b", "b", 0),
+            ClosureRow::new("a", "b", 1),
+            ClosureRow::new("b", "a", 1),
+        ];
+        assert_eq!(build(rows).unwrap_err(), RelationalError::NoRoot);
+        let rows = vec![
+            ClosureRow::new("r", "r", 0),
+            ClosureRow::new("a", "a", 0),
+            ClosureRow::new("b", "b", 0),
+            ClosureRow::new("a", "b", 1),
+            ClosureRow::new("b", "a", 1),
+        ];
+        assert!(matches!(
+            build(rows).unwrap_err(),
+            RelationalError::Unreachable(_)
+        ));
+    }
+
+    #[test]
+    fn test_should_round_trip_nested_set() {
+        let tree = tree();
+        let mut rows = tree.nested_set();
+        assert_eq!(
+            rows,
+            vec![
+                NestedSetRow::new("/", 1, 14, 0),
+                NestedSetRow::new("/bin", 2, 7, 1),
+                NestedSetRow::new("/bin/ls", 3, 4, 2),
+                NestedSetRow::new("/bin/pwd", 5, 6, 2),
+                NestedSetRow::new("/tmp", 8, 13, 1),
+                NestedSetRow::new("/tmp/a", 9, 12, 2),
+                NestedSetRow::new("/tmp/a/b", 10, 11, 3),
+            ]
+        );
+        rows.reverse();
+        let value = |x: &&str| *tree.root().query(x).unwrap().value();
+        assert_eq!(Tree::from_nested_set(rows, value).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_should_validate_nested_set() {
+        let build = |rows: Vec<NestedSetRow<&'static str>>| Tree::from_nested_set(rows, |_| ());
+        assert_eq!(build(vec![]).unwrap_err(), RelationalError::Empty);
+        assert_eq!(
+            build(vec![
+                NestedSetRow::new("a", 1, 6, 0),
+                NestedSetRow::new("b", 2, 4, 1),
+                NestedSetRow::new("c", 3, 5, 2),
+            ])
+            .unwrap_err(),
+            RelationalError::OverlappingIntervals("b", "c")
+        );
+        assert_eq!(
+            build(vec![
+                NestedSetRow::new("a", 1, 6, 0),
+                NestedSetRow::new("b", 4, 4, 1),
+            ])
+            .unwrap_err(),
+            RelationalError::InvalidInterval("b")
+        );
+        assert_eq!(
+            build(vec![
+                NestedSetRow::new("a", 1, 4, 0),
+                NestedSetRow::new("a", 2, 3, 1),
+            ])
+            .unwrap_err(),
+            RelationalError::DuplicateId("a")
+        );
+        assert_eq!(
+            build(vec![
+                NestedSetRow::new("a", 1, 4, 0),
+                NestedSetRow::new("b", 2, 3, 2),
+            ])
+            .unwrap_err(),
+            RelationalError::InvalidDepth("b")
+        );
+        assert_eq!(
+            build(vec![
+                NestedSetRow::new("a", 1, 2, 0),
+                NestedSetRow::new("b", 3, 4, 0),
+            ])
+            .unwrap_err(),
+            RelationalError::MultipleRoots
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! # Set
+//!
+//! Union, intersection and difference of trees keyed on node ids
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::{FilterMode, Node, Tree};
+
+impl<U: PartialEq + Clone, T: Clone> Node<U, T> {
+    /// Returns the union of this [`Node`] and `other`.
+    ///
+    /// The roots are always merged, while the other nodes are matched by id anywhere in the tree, as [`Node::query`] does.
+    /// Values of the nodes in both trees are combined with `combine`, which receives the value of this tree first;
+    /// the nodes only in `other` are added, in pre-order, as children of their parent.
+    ///
+    /// ```rust
+    /// use orange_trees::Node;
+    ///
+    /// let a: Node<&str, usize> = Node::new("/", 1).with_child(Node::new("/bin", 2));
+    /// let b: Node<&str, usize> = Node::new("/", 3).with_child(Node::new("/tmp", 4));
+    /// let union = a.union(&b, |x, y| x + y);
+    /// assert_eq!(union.value(), &4);
+    /// assert_eq!(union.query(&"/tmp").unwrap().value(), &4);
+    /// ```
+    pub fn union<F>(&self, other: &Self, mut combine: F) -> Self
+    where
+        F: FnMut(&T, &T) -> T,
+    {
+        /// Private recursive call for union
+        fn union_r<U: PartialEq + Clone, T: Clone, F: FnMut(&T, &T) -> T>(
+            union: &mut Node<U, T>,
+            parent: &U,
+            node: &Node<U, T>,
+            combine: &mut F,
+        ) {
+            for child in node.children.iter() {
+                match union.query_mut(&child.id) {
+                    Some(existing) => existing.value = combine(&existing.value, &child.value),
+                    None => union
+                        .query_mut(parent)
+                        .unwrap()
+                        .add_child(Node::new(child.id.clone(), child.value.clone())),
+                }
+                union_r(union, &child.id, child, combine);
+            }
+        }
+        let mut union = self.clone();
+        union.value = combine(&self.value, &other.value);
+        union_r(&mut union, &self.id, other, &mut combine);
+        union
+    }
+
+    /// Returns the nodes of this [`Node`] which also exist in `other`, together with their ancestors,
+    /// so that their paths are preserved. Values are the ones of this tree.
+    /// Returns `None` if no node exists in both trees
+    pub fn intersection(&self, other: &Self) -> Option<Self>
+    where
+        U: Eq + Hash,
+    {
+        let ids = other.ids();
+        self.filtered(&|x| ids.contains(&x.id), FilterMode::KeepAncestors)
+    }
+
+    /// Returns the nodes of this [`Node`] which don't exist in `other`, together with their ancestors,
+    /// so that their paths are preserved. Returns `None` if all the nodes exist in `other`
+    pub fn difference(&self, other: &Self) -> Option<Self>
+    where
+        U: Eq + Hash,
+    {
+        let ids = other.ids();
+        self.filtered(&|x| !ids.contains(&x.id), FilterMode::KeepAncestors)
+    }
+
+    /// Returns the ids of the nodes of this branch
+    fn ids(&self) -> HashSet<&U>
+    where
+        U: Eq + Hash,
+    {
+        self.find(&|_| true).into_iter().map(|x| &x.id).collect()
+    }
+}
+
+impl<U: PartialEq + Clone, T: Clone> Tree<U, T> {
+    /// Returns the union of this [`Tree`] and `other`, combining the values of the nodes in both trees
+    /// with `combine`. See [`Node::union`]
+    pub fn union<F>(&self, other: &Self, combine: F) -> Self
+    where
+        F: FnMut(&T, &T) -> T,
+    {
+        Tree::new(self.root.union(other.root(), combine))
+    }
+
+    /// Returns the nodes of this [`Tree`] which also exist in `other`, with their paths. See [`Node::intersection`]
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let a: Tree<&str, usize> = Tree::new(
+    ///     Node::new("/", 0)
+    ///         .with_child(Node::new("/bin", 1).with_child(Node::new("/bin/ls", 2)))
+    ///         .with_child(Node::new("/tmp", 3)),
+    /// );
+    /// let b: Tree<&str, usize> = Tree::new(Node::new("/", 0).with_child(Node::new("/bin/ls", 2)));
+    /// assert_eq!(a.intersection(&b).unwrap().root().count(), 3);
+    /// assert_eq!(a.difference(&b).unwrap().root().count(), 3);
+    /// assert!(b.difference(&a).is_none());
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Option<Self>
+    where
+        U: Eq + Hash,
+    {
+        self.root.intersection(other.root()).map(Tree::new)
+    }
+
+    /// Returns the nodes of this [`Tree`] which don't exist in `other`, with their paths. See [`Node::difference`]
+    pub fn difference(&self, other: &Self) -> Option<Self>
+    where
+        U: Eq + Hash,
+    {
+        self.root.difference(other.root()).map(Tree::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn ours() -> Tree<&'static str, Vec<&'static str>> {
+        Tree::new(
+            Node::new("/", vec!["r"])
+                .with_child(
+                    Node::new("/bin", vec!["r", "x"])
+                        .with_child(Node::new("/bin/ls", vec!["x"]))
+                        .with_child(Node::new("/bin/pwd", vec!["x"])),
+                )
+                .with_child(Node::new("/tmp", vec!["w"])),
+        )
+    }
+
+    fn theirs() -> Tree<&'static str, Vec<&'static str>> {
+        Tree::new(
+            Node::new("/", vec!["w"])
+                .with_child(
+                    Node::new("/bin", vec!["r"]).with_child(Node::new("/bin/ls", vec!["r"])),
+                )
+                .with_child(
+                    Node::new("/home", vec!["r"])
+                        .with_child(Node::new("/home/omar", vec!["w"]))
+                        // moved in theirs
+                        .with_child(Node::new("/bin/pwd", vec!["w"])),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_should_compute_union() {
+        let union = ours().union(&theirs(), |x, y| {
+            let mut permissions = x.clone();
+            permissions.extend(y.iter().filter(|p| !x.contains(p)));
+            permissions
+        });
+        assert_eq!(
+            union,
+            Tree::new(
+                Node::new("/", vec!["r", "w"])
+                    .with_child(
+                        Node::new("/bin", vec!["r", "x"])
+                            .with_child(Node::new("/bin/ls", vec!["x", "r"]))
+                            .with_child(Node::new("/bin/pwd", vec!["x", "w"])),
+                    )
+                    .with_child(Node::new("/tmp", vec!["w"]))
+                    .with_child(
+                        Node::new("/home", vec!["r"])
+                            .with_child(Node::new("/home/omar", vec!["w"]))
+                    ),
+            )
+        );
+        // union with itself
+        assert_eq!(ours().union(&ours(), |x, _| x.clone()), ours());
+    }
+
+    #[test]
+    fn test_should_compute_intersection_and_difference() {
+        assert_eq!(
+            ours().intersection(&theirs()).unwrap(),
+            Tree::new(
+                Node::new("/", vec!["r"]).with_child(
+                    Node::new("/bin", vec!["r", "x"])
+                        .with_child(Node::new("/bin/ls", vec!["x"]))
+                        .with_child(Node::new("/bin/pwd", vec!["x"])),
+                ),
+            )
+        );
+        assert_eq!(ours().intersection(&ours()).unwrap(), ours());
+        assert_eq!(
+            ours().difference(&theirs()).unwrap(),
+            Tree::new(Node::new("/", vec!["r"]).with_child(Node::new("/tmp", vec!["w"])))
+        );
+        assert_eq!(
+            theirs().difference(&ours()).unwrap(),
+            Tree::new(Node::new("/", vec!["w"]).with_child(
+                Node::new("/home", vec!["r"]).with_child(Node::new("/home/omar", vec!["w"]))
+            ),)
+        );
+        assert!(ours().difference(&ours()).is_none());
+        let other = Tree::new(Node::new("root", vec![]));
+        assert!(ours().intersection(&other).is_none());
+    }
+}
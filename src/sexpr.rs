@@ -0,0 +1,446 @@
+//! # S-expressions
+//!
+//! Read and write trees as S-expressions (e.g. `(root "v" (a "x") (b "y" (c "z")))`)
+
+use std::fmt;
+
+use crate::{Node, Tree};
+
+/// Describes an error which occurred while parsing an S-expression.
+/// Lines and columns start from `1`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SexprError {
+    /// The input ended before the tree was complete
+    UnexpectedEnd { line: usize, column: usize },
+    /// Found an unexpected character
+    UnexpectedChar {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// The string starting at this position is never closed
+    UnterminatedString { line: usize, column: usize },
+    /// Unknown escape sequence in a string
+    InvalidEscape { line: usize, column: usize },
+    /// The node starting at this position has the same id as one of its siblings
+    DuplicateId { line: usize, column: usize },
+}
+
+impl fmt::Display for SexprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd { line, column } => {
+                write!(f, "unexpected end of input at {line}:{column}")
+            }
+            Self::UnexpectedChar {
+                line,
+                column,
+                found,
+            } => write!(f, "unexpected character '{found}' at {line}:{column}"),
+            Self::UnterminatedString { line, column } => {
+                write!(f, "unterminated string at {line}:{column}")
+            }
+            Self::InvalidEscape { line, column } => {
+                write!(f, "invalid escape sequence at {line}:{column}")
+            }
+            Self::DuplicateId { line, column } => write!(f, "duplicate id at {line}:{column}"),
+        }
+    }
+}
+
+impl std::error::Error for SexprError {}
+
+impl Tree<String, String> {
+    /// Parse a [`Tree`] from an S-expression.
+    ///
+    /// Each node is a list made of the id, the value and the children (e.g. `(root "v" (a "x"))`),
+    /// which is the same shape of the [`crate::node`] macro. Siblings must have different ids.
+    /// Ids and values may be either bare atoms or quoted strings; `;` starts a comment running to the end of the line.
+    ///
+    /// ```rust
+    /// use orange_trees::Tree;
+    ///
+    /// let tree = Tree::from_sexpr(r#"(root "v" (a "x") (b "y" (c "z")))"#).unwrap();
+    /// assert_eq!(tree.root().count(), 4);
+    /// assert_eq!(tree.root().query(&"c".to_string()).unwrap().value(), "z");
+    /// ```
+    pub fn from_sexpr(text: &str) -> Result<Self, SexprError> {
+        Self::parse_sexpr(text, |x| x.to_string(), |x| x.to_string())
+    }
+}
+
+impl<U: PartialEq, T> Tree<U, T> {
+    /// Parse a [`Tree`] from an S-expression, building ids and values with `id_fn` and `value_fn`.
+    ///
+    /// See [`Tree::from_sexpr`] for the syntax.
+    pub fn parse_sexpr<I, V>(text: &str, mut id_fn: I, mut value_fn: V) -> Result<Self, SexprError>
+    where
+        I: FnMut(&str) -> U,
+        V: FnMut(&str) -> T,
+    {
+        let mut parser = Parser::new(text);
+        let root = parser.node(&mut id_fn, &mut value_fn)?;
+        parser.skip_blanks();
+        match parser.peek() {
+            None => Ok(Tree::new(root)),
+            Some(found) => Err(parser.unexpected_char(found)),
+        }
+    }
+}
+
+impl<U: PartialEq + fmt::Display, T: fmt::Display> Tree<U, T> {
+    /// Write the [`Tree`] as an S-expression.
+    ///
+    /// Nodes which fit in `width` columns are written on a single line,
+    /// otherwise each child is written on its own line, indented by two spaces.
+    ///
+    /// ```rust
+    /// use orange_trees::{Node, Tree};
+    ///
+    /// let tree: Tree<&str, &str> = Tree::new(
+    ///     Node::new("root", "v")
+    ///         .with_child(Node::new("a", "x"))
+    ///         .with_child(Node::new("b", "y").with_child(Node::new("c", "z"))),
+    /// );
+    /// assert_eq!(tree.to_sexpr(80), r#"(root "v" (a "x") (b "y" (c "z")))"#);
+    /// assert_eq!(tree.to_sexpr(20), "(root \"v\"\n  (a \"x\")\n  (b \"y\" (c \"z\")))");
+    /// ```
+    pub fn to_sexpr(&self, width: usize) -> String {
+        /// Private recursive call for to_sexpr
+        fn to_sexpr_r<U: fmt::Display, T: fmt::Display>(
+            node: &Node<U, T>,
+            indent: usize,
+            width: usize,
+            sexpr: &mut String,
+        ) {
+            let flat = flat_sexpr(node);
+            if indent + flat.chars().count() <= width || node.children.is_empty() {
+                sexpr.push_str(&flat);
+                return;
+            }
+            sexpr.push('(');
+            sexpr.push_str(&head(node));
+            for child in node.children.iter() {
+                sexpr.push('\n');
+                sexpr.push_str(&" ".repeat(indent + 2));
+                to_sexpr_r(child, indent + 2, width, sexpr);
+            }
+            sexpr.push(')');
+        }
+        let mut sexpr = String::new();
+        to_sexpr_r(self.root(), 0, width, &mut sexpr);
+        sexpr
+    }
+}
+
+/// Write the node on a single line
+fn flat_sexpr<U: fmt::Display, T: fmt::Display>(node: &Node<U, T>) -> String {
+    let mut sexpr = format!("({}", head(node));
+    for child in node.children.iter() {
+        sexpr.push(' ');
+        sexpr.push_str(&flat_sexpr(child));
+    }
+    sexpr.push(')');
+    sexpr
+}
+
+/// Write the id and the value of the node
+fn head<U: fmt::Display, T: fmt::Display>(node: &Node<U, T>) -> String {
+    let id = node.id.to_string();
+    let id = if !id.is_empty() && id.chars().all(is_atom_char) {
+        id
+    } else {
+        quote(&id)
+    };
+    format!("{id} {}", quote(&node.value.to_string()))
+}
+
+/// Quote and escape a string
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns whether `c` can be part of a bare atom
+fn is_atom_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ';')
+}
+
+/// Recursive descent parser for S-expressions
+struct Parser {
+    input: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            input: text.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Parse a node with its children
+    fn node<U, T, I, V>(
+        &mut self,
+        id_fn: &mut I,
+        value_fn: &mut V,
+    ) -> Result<Node<U, T>, SexprError>
+    where
+        U: PartialEq,
+        I: FnMut(&str) -> U,
+        V: FnMut(&str) -> T,
+    {
+        self.skip_blanks();
+        self.expect('(')?;
+        let id = self.atom()?;
+        let value = self.atom()?;
+        let mut node = Node::new(id_fn(&id), value_fn(&value));
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                Some(')') => {
+                    self.bump();
+                    return Ok(node);
+                }
+                Some(_) => {
+                    let (line, column) = (self.line, self.column);
+                    let child = self.node(id_fn, value_fn)?;
+                    if node.children.iter().any(|x| x.id == child.id) {
+                        return Err(SexprError::DuplicateId { line, column });
+                    }
+                    node.children.push(child);
+                }
+                None => return Err(self.unexpected_end()),
+            }
+        }
+    }
+
+    /// Parse a bare atom or a quoted string
+    fn atom(&mut self) -> Result<String, SexprError> {
+        self.skip_blanks();
+        match self.peek() {
+            Some('"') => self.string(),
+            Some(c) if is_atom_char(c) => {
+                let mut atom = String::new();
+                while let Some(c) = self.peek().filter(|x| is_atom_char(*x)) {
+                    atom.push(c);
+                    self.bump();
+                }
+                Ok(atom)
+            }
+            Some(c) => Err(self.unexpected_char(c)),
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    /// Parse a quoted string
+    fn string(&mut self) -> Result<String, SexprError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(SexprError::UnterminatedString { line, column }),
+                Some('"') => {
+                    self.bump();
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    string.push(match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        _ => return Err(SexprError::InvalidEscape { line, column }),
+                    });
+                    self.bump();
+                }
+                Some(c) => {
+                    string.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Consume the next character, which must be `expected`
+    fn expect(&mut self, expected: char) -> Result<(), SexprError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.unexpected_char(c)),
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    /// Skip whitespace and comments
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ';' {
+                while self.peek().map(|x| x != '\n').unwrap_or(false) {
+                    self.bump();
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position).copied()
+    }
+
+    /// Move to the next character, keeping track of line and column
+    fn bump(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.position += 1;
+    }
+
+    fn unexpected_char(&self, found: char) -> SexprError {
+        SexprError::UnexpectedChar {
+            line: self.line,
+            column: self.column,
+            found,
+        }
+    }
+
+    fn unexpected_end(&self) -> SexprError {
+        SexprError::UnexpectedEnd {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_should_parse_sexpr() {
+        let tree = Tree::from_sexpr(
+            r#"
+; fixture
+(/ "/"
+  (/bin bin/ (/bin/ls "ls") (/bin/pwd "pwd"))
+  ("/home omar" "with \"quotes\"\n"))
+"#,
+        )
+        .unwrap();
+        assert_eq!(tree.root().id(), "/");
+        assert_eq!(tree.root().count(), 5);
+        assert_eq!(
+            tree.root().query(&"/bin".to_string()).unwrap().value(),
+            "bin/"
+        );
+        assert_eq!(
+            tree.root().node_by_route(&[1]).unwrap().value(),
+            "with \"quotes\"\n"
+        );
+        let tree: Tree<usize, usize> = Tree::parse_sexpr(
+            "(0 1 (1 2) (2 4))",
+            |x| x.parse().unwrap(),
+            |x| x.parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tree.root().query(&2).unwrap().value(), &4);
+    }
+
+    #[test]
+    fn test_should_write_sexpr() {
+        let tree = Tree::new(
+            Node::new("root", "v")
+                .with_child(Node::new("a b", "x"))
+                .with_child(
+                    Node::new("b", "\"y\"")
+                        .with_child(Node::new("c", "z"))
+                        .with_child(Node::new("d", "w")),
+                ),
+        );
+        assert_eq!(
+            tree.to_sexpr(80),
+            r#"(root "v" ("a b" "x") (b "\"y\"" (c "z") (d "w")))"#
+        );
+        let pretty = tree.to_sexpr(16);
+        assert_eq!(
+            pretty,
+            r#"(root "v"
+  ("a b" "x")
+  (b "\"y\""
+    (c "z")
+    (d "w")))"#
+        );
+        // round trip
+        let parsed = Tree::from_sexpr(&pretty).unwrap();
+        assert_eq!(parsed.to_sexpr(80), tree.to_sexpr(80));
+    }
+
+    #[test]
+    fn test_should_report_sexpr_errors() {
+        assert_eq!(
+            Tree::from_sexpr("(a \"x\"\n  (b \"y\")").unwrap_err(),
+            SexprError::UnexpectedEnd {
+                line: 2,
+                column: 10
+            }
+        );
+        assert_eq!(
+            Tree::from_sexpr("(a \"x\")\n(b \"y\")").unwrap_err(),
+            SexprError::UnexpectedChar {
+                line: 2,
+                column: 1,
+                found: '('
+            }
+        );
+        assert_eq!(
+            Tree::from_sexpr("(a)").unwrap_err(),
+            SexprError::UnexpectedChar {
+                line: 1,
+                column: 3,
+                found: ')'
+            }
+        );
+        assert_eq!(
+            Tree::from_sexpr("(a \"x)").unwrap_err(),
+            SexprError::UnterminatedString { line: 1, column: 4 }
+        );
+        assert_eq!(
+            Tree::from_sexpr("(a \"\\x\")").unwrap_err(),
+            SexprError::InvalidEscape { line: 1, column: 5 }
+        );
+        assert_eq!(
+            Tree::from_sexpr("(r \"v\" (a \"1\" (x \"2\"))\n  (a \"3\" (y \"4\")))").unwrap_err(),
+            SexprError::DuplicateId { line: 2, column: 3 }
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! # Shared
+//!
+//! Immutable trees where identical subtrees are stored once and shared
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::{Node, Tree};
+
+/// The value and the children of a subtree, regardless of ids, stored once for all the identical subtrees
+#[derive(Debug, Eq, PartialEq)]
+pub struct SharedShape<T> {
+    value: T,
+    children: Vec<Arc<SharedShape<T>>>,
+    count: usize,
+}
+
+/// An immutable tree where subtrees with the same values and the same shape are stored once
+/// and shared through [`Arc`]s, making a DAG.
+///
+/// Ids are kept out of the shared shapes, in pre-order, so the memory used by the shapes grows with
+/// the number of distinct subtrees
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SharedTree<U, T> {
+    root: Arc<SharedShape<T>>,
+    ids: Vec<U>,
+    unique: usize,
+}
+
+/// A node of a [`SharedTree`], made of its shape and of the id of this occurrence
+#[derive(Debug)]
+pub struct SharedNode<'a, U, T> {
+    ids: &'a [U],
+    shape: &'a Arc<SharedShape<T>>,
+    /// Position of the node in pre-order
+    index: usize,
+}
+
+impl<U, T> Clone for SharedNode<'_, U, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U, T> Copy for SharedNode<'_, U, T> {}
+
+/// An interned shape, compared by value and identity of its children
+struct Interned<T>(Arc<SharedShape<T>>);
+
+impl<T: Hash> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.value.hash(state);
+        self.0
+            .children
+            .iter()
+            .for_each(|x| Arc::as_ptr(x).hash(state));
+    }
+}
+
+impl<T: Eq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.value == other.0.value
+            && self.0.children.len() == other.0.children.len()
+            && self
+                .0
+                .children
+                .iter()
+                .zip(other.0.children.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl<T: Eq> Eq for Interned<T> {}
+
+impl<T> SharedShape<T> {
+    /// Get reference to the value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Get reference to the shapes of the children
+    pub fn children(&self) -> &[Arc<SharedShape<T>>] {
+        &self.children
+    }
+
+    /// Count items in the expanded subtree (including self)
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<'a, U: PartialEq, T> SharedNode<'a, U, T> {
+    /// Get reference to id
+    pub fn id(&self) -> &'a U {
+        &self.ids[self.index]
+    }
+
+    /// Get reference to node value
+    pub fn value(&self) -> &'a T {
+        &self.shape.value
+    }
+
+    /// Get reference to the shape of the subtree, shared with the identical subtrees
+    pub fn shape(&self) -> &'a Arc<SharedShape<T>> {
+        self.shape
+    }
+
+    /// Get iterator over node children
+    pub fn children(&self) -> impl Iterator<Item = SharedNode<'a, U, T>> {
+        let ids = self.ids;
+        self.shape
+            .children
+            .iter()
+            .scan(self.index + 1, move |index, shape| {
+                let node = SharedNode {
+                    ids,
+                    shape,
+                    index: *index,
+                };
+                *index += shape.count;
+                Some(node)
+            })
+    }
+
+    /// Returns whether this [`SharedNode`] is a leaf (which means it has no children)
+    pub fn is_leaf(&self) -> bool {
+        self.shape.children.is_empty()
+    }
+
+    /// Search for `id` inside [`SharedNode`] and return it, if exists
+    pub fn query(&self, id: &U) -> Option<Self> {
+        let end = self.index + self.shape.count;
+        let index = self.ids[self.index..end].iter().position(|x| x == id)? + self.index;
+        // descend into the child whose range contains `index`
+        let mut node = *self;
+        while node.index != index {
+            node = node
+                .children()
+                .find(|x| index < x.index + x.shape.count)
+                .unwrap();
+        }
+        Some(node)
+    }
+
+    /// Find nodes, in this branch, by predicate
+    pub fn find<P>(&self, predicate: &P) -> Vec<Self>
+    where
+        P: Fn(&Self) -> bool,
+    {
+        let mut result: Vec<Self> = Vec::new();
+        if predicate(self) {
+            result.push(*self);
+        }
+        self.children()
+            .for_each(|x| result.extend(x.find(predicate)));
+        result
+    }
+
+    /// Count items in the tree (including self)
+    pub fn count(&self) -> usize {
+        self.shape.count
+    }
+
+    /// Expand this [`SharedNode`] into a plain [`Node`]
+    pub fn to_node(&self) -> Node<U, T>
+    where
+        U: Clone,
+        T: Clone,
+    {
+        Node {
+            id: self.id().clone(),
+            value: self.value().clone(),
+            children: self.children().map(|x| x.to_node()).collect(),
+        }
+    }
+}
+
+impl<U: PartialEq, T> SharedTree<U, T> {
+    /// Instantiates a new [`SharedTree`] from `tree`, sharing its subtrees which have the same values
+    /// and the same shape, regardless of their ids.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use orange_trees::{Node, SharedTree, Tree};
+    ///
+    /// let tree: Tree<&str, &str> = Tree::new(
+    ///     Node::new("form", "login")
+    ///         .with_child(Node::new("ok", "button").with_child(Node::new("ok/icon", "check.svg")))
+    ///         .with_child(Node::new("apply", "button").with_child(Node::new("apply/icon", "check.svg"))),
+    /// );
+    /// let shared = SharedTree::from_tree(&tree);
+    /// assert_eq!(shared.root().count(), 5);
+    /// assert_eq!(shared.unique_count(), 3);
+    /// let buttons: Vec<_> = shared.root().children().collect();
+    /// assert!(Arc::ptr_eq(buttons[0].shape(), buttons[1].shape()));
+    /// assert_eq!(buttons[1].children().next().unwrap().id(), &"apply/icon");
+    /// assert_eq!(shared.to_tree(), tree);
+    /// ```
+    pub fn from_tree(tree: &Tree<U, T>) -> Self
+    where
+        U: Clone,
+        T: Eq + Hash + Clone,
+    {
+        /// Private recursive call for from_tree
+        fn intern_r<U, T>(
+            node: &Node<U, T>,
+            ids: &mut Vec<U>,
+            interned: &mut HashSet<Interned<T>>,
+        ) -> Arc<SharedShape<T>>
+        where
+            U: Clone,
+            T: Eq + Hash + Clone,
+        {
+            ids.push(node.id.clone());
+            let children: Vec<Arc<SharedShape<T>>> = node
+                .children
+                .iter()
+                .map(|x| intern_r(x, ids, interned))
+                .collect();
+            let candidate = Interned(Arc::new(SharedShape {
+                value: node.value.clone(),
+                count: children.iter().map(|x| x.count).sum::<usize>() + 1,
+                children,
+            }));
+            match interned.get(&candidate) {
+                Some(existing) => existing.0.clone(),
+                None => {
+                    let shape = candidate.0.clone();
+                    interned.insert(candidate);
+                    shape
+                }
+            }
+        }
+        let mut ids = Vec::new();
+        let mut interned = HashSet::new();
+        let root = intern_r(tree.root(), &mut ids, &mut interned);
+        Self {
+            root,
+            ids,
+            unique: interned.len(),
+        }
+    }
+
+    /// Returns the root node of the tree
+    pub fn root(&self) -> SharedNode<'_, U, T> {
+        SharedNode {
+            ids: &self.ids,
+            shape: &self.root,
+            index: 0,
+        }
+    }
+
+    /// Returns the ids of the nodes, in pre-order
+    pub fn ids(&self) -> &[U] {
+        &self.ids
+    }
+
+    /// Returns the number of distinct shapes stored, which is at most the number of nodes of the tree
+    pub fn unique_count(&self) -> usize {
+        self.unique
+    }
+
+    /// Expand this [`SharedTree`] into a plain [`Tree`]
+    pub fn to_tree(&self) -> Tree<U, T>
+    where
+        U: Clone,
+        T: Clone,
+    {
+        Tree::new(self.root().to_node())
+    }
+}
+
+impl<U, T> From<&Tree<U, T>> for SharedTree<U, T>
+where
+    U: PartialEq + Clone,
+    T: Eq + Hash + Clone,
+{
+    fn from(tree: &Tree<U, T>) -> Self {
+        Self::from_tree(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// A table with `rows` rows of two cells, with the same values and different ids
+    fn table(rows: usize) -> Tree<String, String> {
+        let mut table = Node::new(String::from("table"), String::from("table"));
+        for i in 0..rows {
+            let id = format!("tr{i}");
+            table.add_child(
+                Node::new(id.clone(), String::from("row"))
+                    .with_child(Node::new(format!("{id}/a"), String::from("a")))
+                    .with_child(Node::new(format!("{id}/b"), String::from("b"))),
+            );
+        }
+        Tree::new(table)
+    }
+
+    /// Collect the distinct shape allocations reachable from `shape`
+    fn allocations<T>(shape: &Arc<SharedShape<T>>, found: &mut HashSet<*const SharedShape<T>>) {
+        if found.insert(Arc::as_ptr(shape)) {
+            shape.children().iter().for_each(|x| allocations(x, found));
+        }
+    }
+
+    #[test]
+    fn test_should_share_identical_subtrees() {
+        let tree = table(100);
+        let shared = SharedTree::from(&tree);
+        assert_eq!(shared.unique_count(), 4);
+        let mut found = HashSet::new();
+        allocations(shared.root().shape(), &mut found);
+        assert_eq!(found.len(), 4);
+        assert_eq!(Arc::strong_count(&shared.root().shape().children()[0]), 100);
+        assert_eq!(shared.ids().len(), tree.root().count());
+        assert_eq!(shared.root().count(), tree.root().count());
+        assert!(shared
+            .root()
+            .children()
+            .all(|x| Arc::ptr_eq(x.shape(), &shared.root().shape().children()[0])));
+        assert_eq!(shared.to_tree(), tree);
+        // subtrees with the same shape but different values are not shared
+        let mut tree = table(2);
+        tree.root_mut().children[1].children[1].set_value(String::from("c"));
+        let shared = SharedTree::from_tree(&tree);
+        assert_eq!(shared.unique_count(), 6);
+        let mut found = HashSet::new();
+        allocations(shared.root().shape(), &mut found);
+        assert_eq!(found.len(), 6);
+        let rows = shared.root().shape().children();
+        assert!(Arc::ptr_eq(&rows[0].children()[0], &rows[1].children()[0]));
+        assert!(!Arc::ptr_eq(&rows[0], &rows[1]));
+        assert_eq!(shared.to_tree(), tree);
+    }
+
+    #[test]
+    fn test_should_traverse_shared_tree() {
+        let shared = SharedTree::from_tree(&table(3));
+        let root = shared.root();
+        assert_eq!(root.id(), "table");
+        assert_eq!(root.value(), "table");
+        assert!(!root.is_leaf());
+        assert_eq!(root.children().count(), 3);
+        let cell = root.query(&String::from("tr2/b")).unwrap();
+        assert_eq!(cell.value(), "b");
+        assert_eq!(cell.id(), "tr2/b");
+        assert_eq!(
+            root.query(&String::from("tr1")).unwrap().to_node(),
+            table(3).root().children()[1]
+        );
+        assert!(root.query(&String::from("th")).is_none());
+        let row = root.children().nth(1).unwrap();
+        assert!(row.query(&String::from("tr0/a")).is_none());
+        assert_eq!(root.find(&|x| x.is_leaf()).len(), 6);
+        assert_eq!(
+            root.find(&|x| x.value() == "b")
+                .into_iter()
+                .map(|x| x.id().as_str())
+                .collect::<Vec<_>>(),
+            vec!["tr0/b", "tr1/b", "tr2/b"]
+        );
+        assert_eq!(row.shape().value(), "row");
+        assert_eq!(row.shape().children().len(), 2);
+        assert_eq!(row.shape().count(), 3);
+    }
+}
@@ -0,0 +1,388 @@
+//! # XML
+//!
+//! Convert XML documents into trees and back
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use ::xml::common::Position;
+use ::xml::name::OwnedName;
+use ::xml::namespace::{NS_XMLNS_PREFIX, NS_XMLNS_URI, NS_XML_PREFIX, NS_XML_URI};
+use ::xml::reader::{EventReader, ParserConfig, XmlEvent};
+
+use crate::{Node, Tree};
+
+/// The value of a node converted from an XML element
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct XmlElement {
+    /// Tag name, including the namespace prefix (e.g. `xsi:string`)
+    pub name: String,
+    /// Attributes in document order; namespace declarations are reported as `xmlns` attributes
+    pub attributes: Vec<(String, String)>,
+    /// Text content of the element, trimmed
+    pub text: String,
+}
+
+impl XmlElement {
+    /// Instantiates a new [`XmlElement`] with no attributes and no text
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            attributes: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    /// Returns the value of the attribute `name`, if set
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, x)| x.as_str())
+    }
+}
+
+/// Describes how node ids are derived from XML elements
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XmlIdStrategy {
+    /// The id is the path of the element, with the position among the siblings with the same name
+    /// (e.g. `/config/server[2]/port[1]`)
+    Path,
+    /// The id is the value of the provided attribute; elements without it, or whose value was already used
+    /// by a previous element, fallback to [`XmlIdStrategy::Path`]
+    Attribute(String),
+    /// The id is the position of the element in document order, starting from `0`
+    Generated,
+}
+
+/// Describes an error which occurred while parsing an XML document.
+/// Lines and columns start from `1`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XmlError {
+    pub line: u64,
+    pub column: u64,
+    pub message: String,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+impl From<::xml::reader::Error> for XmlError {
+    fn from(e: ::xml::reader::Error) -> Self {
+        Self {
+            line: e.position().row + 1,
+            column: e.position().column + 1,
+            message: e.msg().to_string(),
+        }
+    }
+}
+
+/// An element which is still open while reading the document
+struct Frame {
+    node: Node<String, XmlElement>,
+    path: String,
+    namespace: BTreeMap<String, String>,
+    /// Number of children met for each name
+    names: HashMap<String, usize>,
+}
+
+impl Tree<String, XmlElement> {
+    /// Parse an XML document into a [`Tree`], where each element is a node.
+    ///
+    /// Comments and processing instructions are ignored. The text chunks of each element are joined as they are,
+    /// also around child elements, and the text is trimmed once the element ends.
+    /// Ids are derived from elements as described by `strategy` and should be unique in the document.
+    ///
+    /// ```rust
+    /// use orange_trees::{Tree, XmlIdStrategy};
+    ///
+    /// let tree = Tree::from_xml(
+    ///     r#"<config><server name="a"><port>80</port></server><server name="b"/></config>"#,
+    ///     XmlIdStrategy::Path,
+    /// ).unwrap();
+    /// let port = tree.root().query(&"/config/server[1]/port[1]".to_string()).unwrap();
+    /// assert_eq!(port.value().text, "80");
+    /// assert_eq!(tree.root().node_by_route(&[1]).unwrap().id(), "/config/server[2]");
+    /// ```
+    pub fn from_xml(text: &str, strategy: XmlIdStrategy) -> Result<Self, XmlError> {
+        let config = ParserConfig::new()
+            .trim_whitespace(false)
+            .cdata_to_characters(true)
+            .ignore_comments(true)
+            .allow_multiple_root_elements(false);
+        let mut reader = EventReader::new_with_config(text.as_bytes(), config);
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut root = None;
+        let mut generated = 0;
+        // ids taken by attribute values
+        let mut used: HashSet<String> = HashSet::new();
+        loop {
+            match reader.next()? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => {
+                    let name = qualified_name(&name);
+                    let (path, parent_namespace) = match stack.last_mut() {
+                        Some(parent) => {
+                            let position = parent.names.entry(name.clone()).or_default();
+                            *position += 1;
+                            (
+                                format!("{}/{name}[{position}]", parent.path),
+                                parent.namespace.clone(),
+                            )
+                        }
+                        None => (
+                            format!("/{name}"),
+                            BTreeMap::from([
+                                (NS_XML_PREFIX.to_string(), NS_XML_URI.to_string()),
+                                (NS_XMLNS_PREFIX.to_string(), NS_XMLNS_URI.to_string()),
+                                (String::new(), String::new()),
+                            ]),
+                        ),
+                    };
+                    let mut element = XmlElement::new(&name);
+                    // report the namespaces declared by this element
+                    for (prefix, uri) in namespace.0.iter() {
+                        if parent_namespace.get(prefix) != Some(uri) {
+                            let attribute = match prefix.as_str() {
+                                "" => NS_XMLNS_PREFIX.to_string(),
+                                prefix => format!("{NS_XMLNS_PREFIX}:{prefix}"),
+                            };
+                            element.attributes.push((attribute, uri.clone()));
+                        }
+                    }
+                    element.attributes.extend(
+                        attributes
+                            .into_iter()
+                            .map(|x| (qualified_name(&x.name), x.value)),
+                    );
+                    let id = match &strategy {
+                        XmlIdStrategy::Path => path.clone(),
+                        XmlIdStrategy::Attribute(attribute) => element
+                            .attribute(attribute)
+                            .filter(|x| used.insert(x.to_string()))
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| path.clone()),
+                        XmlIdStrategy::Generated => generated.to_string(),
+                    };
+                    generated += 1;
+                    stack.push(Frame {
+                        node: Node::new(id, element),
+                        path,
+                        namespace: namespace.0,
+                        names: HashMap::new(),
+                    });
+                }
+                XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => {
+                    if let Some(frame) = stack.last_mut() {
+                        frame.node.value.text.push_str(&text);
+                    }
+                }
+                XmlEvent::EndElement { .. } => {
+                    let mut frame = stack.pop().unwrap();
+                    frame.node.value.text = frame.node.value.text.trim().to_string();
+                    match stack.last_mut() {
+                        Some(parent) => parent.node.children.push(frame.node),
+                        None => root = Some(frame.node),
+                    }
+                }
+                XmlEvent::EndDocument => break,
+                _ => {}
+            }
+        }
+        // the reader fails if the document has no root
+        Ok(Tree::new(root.unwrap()))
+    }
+}
+
+impl<U: PartialEq> Tree<U, XmlElement> {
+    /// Serialize the [`Tree`] into an XML document, indented by two spaces per level.
+    ///
+    /// Attribute values and texts are escaped; names are written as they are.
+    pub fn to_xml(&self) -> String {
+        /// Private recursive call for to_xml
+        fn to_xml_r<U>(node: &Node<U, XmlElement>, level: usize, xml: &mut String) {
+            let element = &node.value;
+            xml.push_str(&"  ".repeat(level));
+            xml.push('<');
+            xml.push_str(&element.name);
+            for (name, value) in element.attributes.iter() {
+                xml.push_str(&format!(" {name}=\"{}\"", escape(value)));
+            }
+            if node.children.is_empty() && element.text.is_empty() {
+                xml.push_str("/>\n");
+                return;
+            }
+            xml.push('>');
+            xml.push_str(&escape(&element.text));
+            if !node.children.is_empty() {
+                xml.push('\n');
+                node.children
+                    .iter()
+                    .for_each(|x| to_xml_r(x, level + 1, xml));
+                xml.push_str(&"  ".repeat(level));
+            }
+            xml.push_str(&format!("</{}>\n", element.name));
+        }
+        let mut xml = String::new();
+        to_xml_r(self.root(), 0, &mut xml);
+        xml
+    }
+}
+
+/// Format a name with its prefix
+fn qualified_name(name: &OwnedName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{prefix}:{}", name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+/// Escape markup characters in texts and attribute values
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0"?>
+<!-- configuration -->
+<config xmlns:x="urn:x" version="2">
+  <server id="main" host="a &amp; b">
+    <port>80</port>
+    <x:tls enabled="true"/>
+  </server>
+  <server id="backup">
+    <port><![CDATA[8080]]></port>
+  </server>
+  <motd>Welcome &lt;user&gt;</motd>
+</config>
+"#;
+
+    #[test]
+    fn test_should_parse_xml() {
+        let tree = Tree::from_xml(DOCUMENT, XmlIdStrategy::Path).unwrap();
+        let ids: Vec<&str> = tree
+            .root()
+            .find(&|_| true)
+            .into_iter()
+            .map(|x| x.id().as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "/config",
+                "/config/server[1]",
+                "/config/server[1]/port[1]",
+                "/config/server[1]/x:tls[1]",
+                "/config/server[2]",
+                "/config/server[2]/port[1]",
+                "/config/motd[1]",
+            ]
+        );
+        let root = tree.root().value();
+        assert_eq!(root.name, "config");
+        assert_eq!(
+            root.attributes,
+            vec![
+                ("xmlns:x".to_string(), "urn:x".to_string()),
+                ("version".to_string(), "2".to_string())
+            ]
+        );
+        let server = tree.root().node_by_route(&[0]).unwrap().value();
+        assert_eq!(server.attribute("host"), Some("a & b"));
+        assert_eq!(
+            tree.root().node_by_route(&[1, 0]).unwrap().value().text,
+            "8080"
+        );
+        assert_eq!(
+            tree.root().node_by_route(&[2]).unwrap().value().text,
+            "Welcome <user>"
+        );
+    }
+
+    #[test]
+    fn test_should_derive_xml_ids() {
+        let tree = Tree::from_xml(DOCUMENT, XmlIdStrategy::Attribute("id".to_string())).unwrap();
+        assert!(tree.root().query(&"backup".to_string()).is_some());
+        assert_eq!(tree.root().node_by_route(&[0]).unwrap().id(), "main");
+        assert_eq!(
+            tree.root().node_by_route(&[0, 0]).unwrap().id(),
+            "/config/server[1]/port[1]"
+        );
+        // duplicate attribute values fallback to paths
+        let tree = Tree::from_xml(
+            r#"<r><s name="a"><p/></s><s name="a"><q/></s></r>"#,
+            XmlIdStrategy::Attribute("name".to_string()),
+        )
+        .unwrap();
+        assert_eq!(tree.root().count(), 5);
+        assert_eq!(tree.root().node_by_route(&[0]).unwrap().id(), "a");
+        assert_eq!(tree.root().node_by_route(&[1]).unwrap().id(), "/r/s[2]");
+        assert_eq!(
+            tree.root().node_by_route(&[1, 0]).unwrap().id(),
+            "/r/s[2]/q[1]"
+        );
+        let tree = Tree::from_xml(DOCUMENT, XmlIdStrategy::Generated).unwrap();
+        assert_eq!(tree.root().id(), "0");
+        assert_eq!(tree.root().node_by_route(&[1, 0]).unwrap().id(), "5");
+    }
+
+    #[test]
+    fn test_should_write_xml() {
+        let tree = Tree::from_xml(DOCUMENT, XmlIdStrategy::Path).unwrap();
+        let xml = tree.to_xml();
+        assert_eq!(
+            xml,
+            r#"<config xmlns:x="urn:x" version="2">
+  <server id="main" host="a &amp; b">
+    <port>80</port>
+    <x:tls enabled="true"/>
+  </server>
+  <server id="backup">
+    <port>8080</port>
+  </server>
+  <motd>Welcome &lt;user&gt;</motd>
+</config>
+"#
+        );
+        assert_eq!(Tree::from_xml(&xml, XmlIdStrategy::Path).unwrap(), tree);
+        // mixed content keeps the whitespace around child elements
+        let tree = Tree::from_xml("<a>hello <b/> world</a>", XmlIdStrategy::Path).unwrap();
+        assert_eq!(tree.root().value().text, "hello  world");
+        let xml = tree.to_xml();
+        assert_eq!(xml, "<a>hello  world\n  <b/>\n</a>\n");
+        assert_eq!(Tree::from_xml(&xml, XmlIdStrategy::Path).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_should_report_xml_errors() {
+        let error = Tree::from_xml("<a>\n  <b></a>", XmlIdStrategy::Path).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(Tree::from_xml("", XmlIdStrategy::Path).is_err());
+        assert!(Tree::from_xml("<a/><b/>", XmlIdStrategy::Path).is_err());
+    }
+}